@@ -0,0 +1,54 @@
+#!/usr/bin/env python3
+"""Reference bot for the mines-rs stdin/stdout protocol: flags obvious mines, chords satisfied
+numbers and otherwise reveals the first hidden cell.
+
+    mines-sim --bot "python3 bots/first_hidden.py"
+"""
+
+import sys
+
+
+def neighbours(board, r, c):
+    for dr in (-1, 0, 1):
+        for dc in (-1, 0, 1):
+            if (dr, dc) != (0, 0) and 0 <= r + dr < len(board) and 0 <= c + dc < len(board[0]):
+                yield r + dr, c + dc
+
+
+def choose(board):
+    for r, row in enumerate(board):
+        for c, cell in enumerate(row):
+            if not cell.isdigit():
+                continue
+            around = list(neighbours(board, r, c))
+            hidden = [(nr, nc) for nr, nc in around if board[nr][nc] == "#"]
+            flagged = sum(board[nr][nc] == "F" for nr, nc in around)
+            if not hidden:
+                continue
+            if int(cell) == flagged:
+                return f"chord {r} {c}"
+            if int(cell) == flagged + len(hidden):
+                return f"flag {hidden[0][0]} {hidden[0][1]}"
+
+    for r, row in enumerate(board):
+        for c, cell in enumerate(row):
+            if cell == "#":
+                return f"reveal {r} {c} guess"
+    return "quit"
+
+
+def main():
+    board = None
+    for line in sys.stdin:
+        line = line.strip()
+        if line == "board":
+            board = []
+        elif line == "end":
+            print(choose(board), flush=True)
+            board = None
+        elif board is not None:
+            board.append(line)
+
+
+if __name__ == "__main__":
+    main()
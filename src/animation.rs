@@ -0,0 +1,239 @@
+//! Plays cell changes out over a moment instead of all at once: a ripple out from the click when
+//! a region opens, a pop when a flag goes down, and a chain of mines plus a screen shake on a
+//! loss. The game itself never waits on them; any key or button skips what's left, and they can
+//! be turned off in the settings screen or with `--no-animations`.
+
+use crate::layout::Layout;
+use crate::minefield::*;
+use crate::spritesheets::*;
+use crate::tilemap::*;
+use crate::viewport::CameraControl;
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::time::Duration;
+
+/// Seconds per cell of distance from the click.
+const RIPPLE_STEP: f32 = 0.02;
+/// Big regions ripple faster so they still open in this long.
+const MAX_RIPPLE: f32 = 0.6;
+/// Seconds between each mine going off.
+const MINE_STEP: f32 = 0.08;
+const MAX_MINE_CHAIN: f32 = 2.0;
+const POP_SECONDS: f32 = 0.15;
+/// How much bigger a flag gets at the height of its pop.
+const POP_SCALE: f32 = 0.4;
+
+#[derive(Resource)]
+pub struct Animations {
+    pub enabled: bool,
+    /// Held cells by when they get drawn, in [`Time::elapsed`].
+    pending: BinaryHeap<Reverse<(Duration, (usize, usize))>>,
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pending: BinaryHeap::new(),
+        }
+    }
+}
+
+impl Animations {
+    pub fn is_playing(&self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
+#[derive(Component)]
+struct Pop(Timer);
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Animations>()
+        .add_systems(
+            Update,
+            (
+                schedule_animations.run_if(resource_exists::<Layout>),
+                release_cells,
+            )
+                .chain()
+                .after(send_minefield_changes)
+                .before(update_minefield_chunks),
+        )
+        .add_systems(Update, animate_pops);
+}
+
+/// Delays for cells going out from `origin`, closest first, spread over at most `max` seconds.
+fn by_distance(
+    cells: &[(usize, usize)],
+    origin: (usize, usize),
+    step: f32,
+    max: f32,
+    ranked: bool,
+) -> Vec<(f32, (usize, usize))> {
+    let distance = |(row, col): (usize, usize)| {
+        Vec2::new(row as f32 - origin.0 as f32, col as f32 - origin.1 as f32).length()
+    };
+
+    let mut cells: Vec<(f32, (usize, usize))> = cells
+        .iter()
+        .map(|&position| (distance(position), position))
+        .collect();
+    cells.sort_by(|a, b| a.0.total_cmp(&b.0));
+    if ranked {
+        for (rank, cell) in cells.iter_mut().enumerate() {
+            cell.0 = rank as f32 + 1.0;
+        }
+    }
+
+    let furthest = cells.last().map_or(0.0, |cell| cell.0);
+    let step = if furthest * step > max {
+        max / furthest
+    } else {
+        step
+    };
+    cells
+        .into_iter()
+        .map(|(distance, position)| (distance * step, position))
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn schedule_animations(
+    mut commands: Commands,
+    mut actions: EventReader<MinefieldAction>,
+    mut changes: EventReader<MinefieldChange>,
+    minefield: Res<Minefield>,
+    layout: Res<Layout>,
+    image: Res<SpriteSheetImage>,
+    sprite_sheet: Res<MinefieldSpriteSheet>,
+    time: Res<Time>,
+    mut animations: ResMut<Animations>,
+    mut held: ResMut<HeldCells>,
+    mut camera_control: ResMut<CameraControl>,
+) {
+    // a chord's ripple starts from the number clicked, not the first neighbour it opened
+    let clicked = actions
+        .read()
+        .filter_map(|action| match *action {
+            MinefieldAction::Reveal(row, col) | MinefieldAction::Chord(row, col) => {
+                Some((row, col))
+            }
+            MinefieldAction::Flag(..) => None,
+        })
+        .last();
+    let mut revealed = vec![];
+    for change in changes.read() {
+        match *change {
+            MinefieldChange::Reset => {
+                animations.pending.clear();
+                held.0.clear();
+                revealed.clear();
+            }
+            MinefieldChange::Revealed(row, col) => revealed.push((row, col)),
+            MinefieldChange::Flagged(row, col)
+                if animations.enabled
+                    && minefield.state == GameState::Playing
+                    && minefield.flags[row][col] =>
+            {
+                let size = layout.sprites.field.size();
+                commands.spawn((
+                    Sprite::from_atlas_image(
+                        image.0.clone(),
+                        TextureAtlas {
+                            layout: sprite_sheet.0.clone(),
+                            index: MinefieldSpriteIndex::Flag.into(),
+                        },
+                    ),
+                    Transform::from_translation(
+                        (layout.cell_position((row, col)) + Vec2::new(size.x, -size.y) / 2.0)
+                            .extend(1.0),
+                    ),
+                    Pop(Timer::from_seconds(POP_SECONDS, TimerMode::Once)),
+                ));
+            }
+            MinefieldChange::Flagged(..) | MinefieldChange::MinesLeft(_) => {}
+        }
+    }
+    if !animations.enabled || revealed.is_empty() {
+        return;
+    }
+
+    let mut delays = vec![];
+    let (mines, opened): (Vec<_>, Vec<_>) = revealed
+        .iter()
+        .partition(|&&(row, col)| minefield.cells[row][col] >= MinefieldSpriteIndex::Mine as u32);
+    if minefield.state == GameState::Lost
+        && let Some(&hit) = mines
+            .iter()
+            .find(|&&(row, col)| minefield.cells[row][col] == MinefieldSpriteIndex::MineHit as u32)
+    {
+        let others: Vec<_> = mines.iter().copied().filter(|&cell| cell != hit).collect();
+        delays.extend(by_distance(&others, hit, MINE_STEP, MAX_MINE_CHAIN, true));
+        camera_control.shake();
+    }
+    delays.extend(by_distance(
+        &opened,
+        clicked.unwrap_or(revealed[0]),
+        RIPPLE_STEP,
+        MAX_RIPPLE,
+        false,
+    ));
+
+    let now = time.elapsed();
+    for (delay, position) in delays {
+        if delay > 0.0 {
+            held.0.insert(position);
+            animations
+                .pending
+                .push(Reverse((now + Duration::from_secs_f32(delay), position)));
+        }
+    }
+}
+
+/// Draws held cells once their time comes, or all of them when any key or button is pressed.
+fn release_cells(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut animations: ResMut<Animations>,
+    mut held: ResMut<HeldCells>,
+    mut camera_control: ResMut<CameraControl>,
+    mut redraws: EventWriter<RedrawCell>,
+) {
+    if !animations.is_playing() {
+        return;
+    }
+
+    let skip = keys.get_just_pressed().next().is_some()
+        || mouse_buttons.get_just_pressed().next().is_some();
+    if skip {
+        camera_control.stop_shaking();
+    }
+
+    let now = time.elapsed();
+    while let Some(&Reverse((due, position))) = animations.pending.peek()
+        && (skip || due <= now)
+    {
+        animations.pending.pop();
+        held.0.remove(&position);
+        redraws.send(RedrawCell(position.0, position.1));
+    }
+}
+
+fn animate_pops(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pops: Query<(Entity, &mut Pop, &mut Transform)>,
+) {
+    for (entity, mut pop, mut transform) in pops.iter_mut() {
+        if pop.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let scale = 1.0 + POP_SCALE * (pop.0.fraction() * std::f32::consts::PI).sin();
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
@@ -0,0 +1,621 @@
+//! Loads sprites straight from an Aseprite file, so the spritesheet never has to be exported.
+//!
+//! The first frame's visible layers are flattened into the image, and named slices give the
+//! atlas regions: `field`, `border`, `face` and `digits` are grids whose `<columns>x<rows>` is
+//! set in the slice's user data text, and `score_frame` is a single sprite.
+//!
+//! Only RGBA sprites with normal blending are supported, which is all the spritesheet uses.
+
+use crate::spritesheets::*;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, RenderAssetUsages};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use flate2::read::ZlibDecoder;
+use std::fmt;
+use std::io::Read;
+
+const HEADER_MAGIC: u16 = 0xa5e0;
+const FRAME_MAGIC: u16 = 0xf1fa;
+const LAYER_CHUNK: u16 = 0x2004;
+const CEL_CHUNK: u16 = 0x2005;
+const USER_DATA_CHUNK: u16 = 0x2020;
+const SLICE_CHUNK: u16 = 0x2022;
+/// The most RGBA bytes the image or a cel can take, 4096x4096 pixels, so a broken header can't
+/// ask for gigabytes.
+const MAX_IMAGE_BYTES: usize = 4096 * 4096 * 4;
+
+/// A named region of the sprite, with the user data text set on it in Aseprite.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slice {
+    pub name: String,
+    pub rect: URect,
+    pub user_data: Option<String>,
+}
+
+/// The first frame of an Aseprite file, flattened.
+#[derive(Clone, Debug)]
+pub struct Aseprite {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA, row by row from the top.
+    pub pixels: Vec<u8>,
+    pub slices: Vec<Slice>,
+}
+
+#[derive(Debug)]
+pub enum AsepriteError {
+    Io(std::io::Error),
+    Invalid(String),
+    Unsupported(String),
+    MissingSlice(&'static str),
+    BadGrid { slice: String, text: String },
+    TooFewSprites(TooFewSprites),
+}
+
+impl fmt::Display for AsepriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to read Aseprite file: {e}"),
+            Self::Invalid(message) => write!(f, "not a valid Aseprite file: {message}"),
+            Self::Unsupported(message) => write!(f, "unsupported Aseprite file: {message}"),
+            Self::MissingSlice(name) => write!(f, "the spritesheet has no '{name}' slice"),
+            Self::BadGrid { slice, text } => write!(
+                f,
+                "slice '{slice}' should have '<columns>x<rows>' as its user data, not '{text}'"
+            ),
+            Self::TooFewSprites(e) => write!(f, "the spritesheet's slices don't fit: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AsepriteError {}
+
+impl From<std::io::Error> for AsepriteError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Little-endian reads over a byte slice, failing instead of panicking at the end.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], AsepriteError> {
+        if n > self.bytes.len() {
+            return Err(AsepriteError::Invalid("unexpected end of file".into()));
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, AsepriteError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, AsepriteError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> Result<i16, AsepriteError> {
+        Ok(i16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, AsepriteError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, AsepriteError> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, AsepriteError> {
+        let len = self.u16()? as usize;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| AsepriteError::Invalid("string isn't UTF-8".into()))
+    }
+}
+
+/// How many bytes a `width` by `height` RGBA image takes, if it's within [`MAX_IMAGE_BYTES`].
+fn image_len(width: u32, height: u32) -> Result<usize, AsepriteError> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|&len| len <= MAX_IMAGE_BYTES)
+        .ok_or_else(|| AsepriteError::Invalid(format!("{width}x{height} is too big an image")))
+}
+
+struct Layer {
+    visible: bool,
+    opacity: u8,
+}
+
+struct Cel {
+    layer: usize,
+    z_index: i16,
+    x: i32,
+    y: i32,
+    opacity: u8,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Aseprite {
+    pub fn parse(bytes: &[u8]) -> Result<Self, AsepriteError> {
+        let mut header = Cursor { bytes };
+        header.u32()?;
+        if header.u16()? != HEADER_MAGIC {
+            return Err(AsepriteError::Invalid("bad header magic".into()));
+        }
+        let frames = header.u16()?;
+        let width = header.u16()? as u32;
+        let height = header.u16()? as u32;
+        let depth = header.u16()?;
+        let layer_opacity_valid = header.u32()? & 1 != 0;
+        if depth != 32 {
+            return Err(AsepriteError::Unsupported(format!(
+                "{depth} bits per pixel, only RGBA (32) is supported"
+            )));
+        }
+        if frames == 0 {
+            return Err(AsepriteError::Invalid("no frames".into()));
+        }
+        let canvas_len = image_len(width, height)?;
+
+        let mut frame = Cursor {
+            bytes: bytes.get(128..).unwrap_or_default(),
+        };
+        let frame_size = frame.u32()? as usize;
+        if frame.u16()? != FRAME_MAGIC {
+            return Err(AsepriteError::Invalid("bad frame magic".into()));
+        }
+        let old_chunks = frame.u16()?;
+        frame.take(4)?;
+        let chunks = match frame.u32()? {
+            0 => old_chunks as u32,
+            n => n,
+        };
+        let mut frame = Cursor {
+            bytes: frame.take(frame_size.saturating_sub(16))?,
+        };
+
+        let mut layers: Vec<Layer> = vec![];
+        // visibility of the groups the next layer could be in, by child level
+        let mut groups: Vec<bool> = vec![];
+        let mut cels = vec![];
+        let mut slices: Vec<Slice> = vec![];
+        let mut last_chunk = 0;
+
+        for _ in 0..chunks {
+            let size = frame.u32()? as usize;
+            let kind = frame.u16()?;
+            let mut chunk = Cursor {
+                bytes: frame.take(size.saturating_sub(6))?,
+            };
+
+            match kind {
+                LAYER_CHUNK => {
+                    let flags = chunk.u16()?;
+                    let layer_type = chunk.u16()?;
+                    let level = chunk.u16()? as usize;
+                    chunk.take(4)?;
+                    let blend_mode = chunk.u16()?;
+                    let opacity = chunk.u8()?;
+                    if blend_mode != 0 && layer_type == 0 {
+                        return Err(AsepriteError::Unsupported(format!(
+                            "blend mode {blend_mode}, only normal (0) is supported"
+                        )));
+                    }
+
+                    groups.truncate(level);
+                    let visible = flags & 1 != 0 && groups.iter().all(|&visible| visible);
+                    if layer_type == 1 {
+                        groups.push(visible);
+                    }
+                    layers.push(Layer {
+                        visible: visible && layer_type == 0,
+                        opacity: if layer_opacity_valid { opacity } else { 255 },
+                    });
+                }
+                CEL_CHUNK => {
+                    let layer = chunk.u16()? as usize;
+                    let x = chunk.i16()? as i32;
+                    let y = chunk.i16()? as i32;
+                    let opacity = chunk.u8()?;
+                    let cel_type = chunk.u16()?;
+                    let z_index = chunk.i16()?;
+                    chunk.take(5)?;
+
+                    let (width, height) = (chunk.u16()? as u32, chunk.u16()? as u32);
+                    let len = image_len(width, height)?;
+                    let pixels = match cel_type {
+                        0 => chunk.take(len)?.to_vec(),
+                        2 => {
+                            // one byte more than fits is enough to tell it's the wrong size
+                            let mut pixels = vec![];
+                            ZlibDecoder::new(chunk.bytes)
+                                .take(len as u64 + 1)
+                                .read_to_end(&mut pixels)?;
+                            pixels
+                        }
+                        _ => {
+                            return Err(AsepriteError::Unsupported(format!(
+                                "cel type {cel_type} on the first frame"
+                            )))
+                        }
+                    };
+                    if pixels.len() != len {
+                        return Err(AsepriteError::Invalid("cel has the wrong size".into()));
+                    }
+
+                    cels.push(Cel {
+                        layer,
+                        z_index,
+                        x,
+                        y,
+                        opacity,
+                        width,
+                        height,
+                        pixels,
+                    });
+                }
+                SLICE_CHUNK => {
+                    let keys = chunk.u32()?;
+                    chunk.take(8)?;
+                    let name = chunk.string()?;
+                    if keys == 0 {
+                        return Err(AsepriteError::Invalid(format!(
+                            "slice '{name}' has no keys"
+                        )));
+                    }
+
+                    // the first key is the one for the first frame, and 9-patch centres and
+                    // pivots don't matter here
+                    chunk.u32()?;
+                    let (x, y) = (chunk.i32()?, chunk.i32()?);
+                    let (w, h) = (chunk.u32()?, chunk.u32()?);
+                    // or its sprites would be drawn from outside the image
+                    if x < 0
+                        || y < 0
+                        || x as u64 + w as u64 > width as u64
+                        || y as u64 + h as u64 > height as u64
+                    {
+                        return Err(AsepriteError::Invalid(format!(
+                            "slice '{name}' ({w}x{h} at {x},{y}) goes past the edge of the \
+                             {width}x{height} sprite"
+                        )));
+                    }
+                    let min = UVec2::new(x as u32, y as u32);
+                    slices.push(Slice {
+                        name,
+                        rect: URect::from_corners(min, min + UVec2::new(w, h)),
+                        user_data: None,
+                    });
+                }
+                USER_DATA_CHUNK if last_chunk == SLICE_CHUNK => {
+                    if chunk.u32()? & 1 != 0
+                        && let Some(slice) = slices.last_mut()
+                    {
+                        slice.user_data = Some(chunk.string()?);
+                    }
+                }
+                _ => {}
+            }
+            last_chunk = kind;
+        }
+
+        let mut pixels = vec![0; canvas_len];
+        cels.sort_by_key(|cel| (cel.layer as i32 + cel.z_index as i32, cel.z_index));
+        for cel in &cels {
+            let Some(layer) = layers.get(cel.layer) else {
+                return Err(AsepriteError::Invalid("cel on a missing layer".into()));
+            };
+            if !layer.visible {
+                continue;
+            }
+
+            let opacity = cel.opacity as f32 / 255.0 * layer.opacity as f32 / 255.0;
+            for row in 0..cel.height {
+                for col in 0..cel.width {
+                    let (x, y) = (cel.x + col as i32, cel.y + row as i32);
+                    if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                        continue;
+                    }
+                    let src = ((row * cel.width + col) * 4) as usize;
+                    let dst = ((y as u32 * width + x as u32) * 4) as usize;
+                    blend(
+                        &mut pixels[dst..dst + 4],
+                        &cel.pixels[src..src + 4],
+                        opacity,
+                    );
+                }
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            slices,
+        })
+    }
+
+    pub fn slice(&self, name: &'static str) -> Result<&Slice, AsepriteError> {
+        self.slices
+            .iter()
+            .find(|slice| slice.name == name)
+            .ok_or(AsepriteError::MissingSlice(name))
+    }
+
+    fn grid(&self, name: &'static str) -> Result<SpriteGrid, AsepriteError> {
+        let slice = self.slice(name)?;
+        let text = slice.user_data.as_deref().unwrap_or("1x1");
+        let bad_grid = || AsepriteError::BadGrid {
+            slice: slice.name.clone(),
+            text: text.to_string(),
+        };
+
+        let (columns, rows) = text.trim().split_once('x').ok_or_else(bad_grid)?;
+        let columns: u32 = columns.parse().map_err(|_| bad_grid())?;
+        let rows: u32 = rows.parse().map_err(|_| bad_grid())?;
+        let size = slice.rect.size();
+        if columns == 0 || rows == 0 || size.x % columns != 0 || size.y % rows != 0 {
+            return Err(bad_grid());
+        }
+
+        Ok(SpriteGrid {
+            size: (size.x / columns, size.y / rows),
+            columns,
+            rows,
+            offset: slice.rect.min.into(),
+        })
+    }
+
+    pub fn sprite_sheet_layout(&self) -> Result<SpriteSheetLayout, AsepriteError> {
+        let score_frame = self.slice("score_frame")?.rect;
+        let layout = SpriteSheetLayout {
+            field: self.grid("field")?,
+            border: self.grid("border")?,
+            face: self.grid("face")?,
+            digits: self.grid("digits")?,
+            score_frame: SpriteRect {
+                size: score_frame.size().into(),
+                offset: score_frame.min.into(),
+            },
+        };
+        layout.check().map_err(AsepriteError::TooFewSprites)?;
+        Ok(layout)
+    }
+
+    pub fn image(&self) -> Image {
+        Image::new(
+            Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            self.pixels.clone(),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+}
+
+/// Normal blending of straight-alpha RGBA.
+fn blend(dst: &mut [u8], src: &[u8], opacity: f32) {
+    let src_alpha = src[3] as f32 / 255.0 * opacity;
+    if src_alpha <= 0.0 {
+        return;
+    }
+    let dst_alpha = dst[3] as f32 / 255.0;
+    let alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+
+    for i in 0..3 {
+        let colour =
+            (src[i] as f32 * src_alpha + dst[i] as f32 * dst_alpha * (1.0 - src_alpha)) / alpha;
+        dst[i] = colour.round() as u8;
+    }
+    dst[3] = (alpha * 255.0).round() as u8;
+}
+
+/// An Aseprite spritesheet: its flattened image (also loadable on its own as
+/// `<file>.aseprite#image`) and the sprite regions from its slices.
+#[derive(Asset, TypePath, Debug)]
+pub struct AsepriteSheet {
+    pub image: Handle<Image>,
+    pub sprites: SpriteSheetLayout,
+}
+
+#[derive(Default)]
+pub struct AsepriteLoader;
+
+impl AssetLoader for AsepriteLoader {
+    type Asset = AsepriteSheet;
+    type Settings = ();
+    type Error = AsepriteError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<AsepriteSheet, AsepriteError> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+        let aseprite = Aseprite::parse(&bytes)?;
+
+        Ok(AsepriteSheet {
+            sprites: aseprite.sprite_sheet_layout()?,
+            image: load_context.add_labeled_asset("image".into(), aseprite.image()),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["aseprite", "ase"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: u16 = 2;
+    const HEIGHT: u16 = 1;
+    const PIXELS: [u8; 8] = [255, 0, 0, 255, 0, 0, 255, 255];
+
+    /// A one-frame, `WIDTH` by `HEIGHT` file with the given `(kind, body)` chunks.
+    fn file(chunks: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut frame = vec![];
+        for (kind, body) in chunks {
+            frame.extend((body.len() as u32 + 6).to_le_bytes());
+            frame.extend(kind.to_le_bytes());
+            frame.extend(body);
+        }
+
+        let mut bytes = vec![0; 128];
+        bytes[4..6].copy_from_slice(&HEADER_MAGIC.to_le_bytes());
+        bytes[6..8].copy_from_slice(&1u16.to_le_bytes());
+        bytes[8..10].copy_from_slice(&WIDTH.to_le_bytes());
+        bytes[10..12].copy_from_slice(&HEIGHT.to_le_bytes());
+        bytes[12..14].copy_from_slice(&32u16.to_le_bytes());
+        bytes[14..18].copy_from_slice(&1u32.to_le_bytes());
+
+        bytes.extend((frame.len() as u32 + 16).to_le_bytes());
+        bytes.extend(FRAME_MAGIC.to_le_bytes());
+        bytes.extend((chunks.len() as u16).to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.extend((chunks.len() as u32).to_le_bytes());
+        bytes.extend(frame);
+        bytes
+    }
+
+    fn layer() -> (u16, Vec<u8>) {
+        // visible, a normal image layer at the top level, opaque
+        (LAYER_CHUNK, vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255])
+    }
+
+    fn cel(width: u16, height: u16, pixels: &[u8]) -> (u16, Vec<u8>) {
+        let mut body = vec![0, 0, 0, 0, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        body.extend(width.to_le_bytes());
+        body.extend(height.to_le_bytes());
+        body.extend(pixels);
+        (CEL_CHUNK, body)
+    }
+
+    #[test]
+    fn flattens_a_raw_cel() {
+        let aseprite = Aseprite::parse(&file(&[layer(), cel(WIDTH, HEIGHT, &PIXELS)])).unwrap();
+        assert_eq!(
+            (aseprite.width, aseprite.height),
+            (WIDTH as u32, HEIGHT as u32)
+        );
+        assert_eq!(aseprite.pixels, PIXELS);
+    }
+
+    #[test]
+    fn rejects_a_truncated_file() {
+        let bytes = file(&[layer(), cel(WIDTH, HEIGHT, &PIXELS)]);
+        for len in 0..bytes.len() {
+            assert!(
+                Aseprite::parse(&bytes[..len]).is_err(),
+                "parsed the first {len} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_a_truncated_chunk() {
+        let (kind, mut body) = layer();
+        body.truncate(5);
+        assert!(Aseprite::parse(&file(&[(kind, body)])).is_err());
+
+        let (kind, mut body) = cel(WIDTH, HEIGHT, &PIXELS);
+        body.pop();
+        assert!(Aseprite::parse(&file(&[layer(), (kind, body)])).is_err());
+    }
+
+    #[test]
+    fn rejects_a_cel_bigger_than_its_chunk() {
+        let bytes = file(&[layer(), cel(u16::MAX, u16::MAX, &PIXELS)]);
+        assert!(Aseprite::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_a_huge_canvas() {
+        let mut bytes = file(&[layer()]);
+        bytes[8..10].copy_from_slice(&u16::MAX.to_le_bytes());
+        bytes[10..12].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert!(matches!(
+            Aseprite::parse(&bytes),
+            Err(AsepriteError::Invalid(_))
+        ));
+    }
+
+    /// A slice called `field` at `x`, `y`, `w` by `h` in the first frame.
+    fn slice(x: i32, y: i32, w: u32, h: u32) -> (u16, Vec<u8>) {
+        let mut body = vec![];
+        body.extend(1u32.to_le_bytes());
+        body.extend([0; 8]);
+        body.extend(5u16.to_le_bytes());
+        body.extend(b"field");
+        body.extend(0u32.to_le_bytes());
+        body.extend(x.to_le_bytes());
+        body.extend(y.to_le_bytes());
+        body.extend(w.to_le_bytes());
+        body.extend(h.to_le_bytes());
+        (SLICE_CHUNK, body)
+    }
+
+    #[test]
+    fn rejects_a_slice_past_the_edge() {
+        let (width, height) = (WIDTH as u32, HEIGHT as u32);
+        for (x, y, w, h) in [
+            (10, 0, u32::MAX, 1),
+            (1, 0, width, height),
+            (0, 0, width, height + 1),
+            (-1, 0, 1, 1),
+            (0, -1, 1, 1),
+        ] {
+            assert!(
+                matches!(
+                    Aseprite::parse(&file(&[slice(x, y, w, h)])),
+                    Err(AsepriteError::Invalid(_))
+                ),
+                "{w}x{h} at {x},{y}"
+            );
+        }
+
+        let aseprite = Aseprite::parse(&file(&[slice(0, 0, width, height)])).unwrap();
+        assert_eq!(
+            aseprite.slice("field").unwrap().rect,
+            URect::new(0, 0, width, height)
+        );
+    }
+
+    #[test]
+    fn stops_inflating_a_cel_that_is_too_big() {
+        use flate2::write::ZlibEncoder;
+        use std::io::Write;
+
+        // a megabyte of zeroes, for a cel that should only have 8 bytes
+        let mut encoder = ZlibEncoder::new(vec![], flate2::Compression::best());
+        encoder.write_all(&vec![0; 1 << 20]).unwrap();
+        let (kind, mut body) = cel(WIDTH, HEIGHT, &encoder.finish().unwrap());
+        body[7] = 2;
+        assert!(matches!(
+            Aseprite::parse(&file(&[layer(), (kind, body)])),
+            Err(AsepriteError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn loads_the_bundled_spritesheet() {
+        let aseprite = Aseprite::parse(include_bytes!("../assets/spritesheet.aseprite")).unwrap();
+        assert!(aseprite.sprite_sheet_layout().is_ok());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::bot_protocol::ProcessStrategy;
+use crate::input::Action;
+use crate::menu::Screen;
+use crate::minefield::*;
+use crate::puzzle_mode::{self, PuzzleMode};
+use crate::settings::Settings;
+use crate::solver::*;
+use crate::strategy::*;
+use bevy::prelude::*;
+use rand::random;
+use std::task::Poll;
+use std::time::Duration;
+
+const DEFAULT_MOVE_DELAY: Duration = Duration::from_millis(150);
+
+/// Insert one of these before adding [`plugin`] to autoplay with your own [`Strategy`].
+#[derive(Resource)]
+pub struct Autoplay {
+    pub enabled: bool,
+    pub strategy: Box<dyn Strategy>,
+    pub timer: Timer,
+    new_game: bool,
+    /// Whether the strategy is still working out a move, which is checked on every frame.
+    waiting: bool,
+}
+
+impl Default for Autoplay {
+    fn default() -> Self {
+        Self::new(Box::new(SolverStrategy::LogicAndGuess))
+    }
+}
+
+impl Autoplay {
+    pub fn new(strategy: Box<dyn Strategy>) -> Self {
+        Self {
+            enabled: false,
+            strategy,
+            timer: Timer::new(DEFAULT_MOVE_DELAY, TimerMode::Repeating),
+            new_game: true,
+            waiting: false,
+        }
+    }
+
+    /// Reads `--autoplay`, `--autoplay-delay=<ms>`, `--autoplay-strategy=<name>` and
+    /// `--autoplay-bot=<command>`.
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut autoplay = Self::default();
+
+        for arg in args {
+            if arg == "--autoplay" {
+                autoplay.enabled = true;
+            } else if let Some(delay) = arg.strip_prefix("--autoplay-delay=") {
+                match delay.parse() {
+                    Ok(ms) => autoplay.timer.set_duration(Duration::from_millis(ms)),
+                    Err(e) => warn!("Ignoring autoplay delay '{delay}': {e}"),
+                }
+            } else if let Some(name) = arg.strip_prefix("--autoplay-strategy=") {
+                match builtin_strategy(name, random()) {
+                    Some(strategy) => autoplay.strategy = strategy,
+                    None => warn!(
+                        "Unknown autoplay strategy '{name}', expected one of {}",
+                        BUILTIN_STRATEGIES.join(", ")
+                    ),
+                }
+            } else if let Some(command) = arg.strip_prefix("--autoplay-bot=") {
+                match ProcessStrategy::spawn(command) {
+                    Ok(strategy) => autoplay.strategy = Box::new(strategy),
+                    Err(e) => warn!("Failed to start autoplay bot '{command}': {e}"),
+                }
+            }
+        }
+
+        autoplay
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    if !app.world().contains_resource::<Autoplay>() {
+        app.insert_resource(Autoplay::from_args(std::env::args().skip(1)));
+    }
+
+    app.add_systems(
+        Update,
+        (toggle_autoplay, autoplay.run_if(in_state(Screen::Game))).chain(),
+    );
+}
+
+fn toggle_autoplay(actions: Res<ButtonInput<Action>>, mut autoplay: ResMut<Autoplay>) {
+    if actions.just_pressed(Action::ToggleAutoplay) {
+        autoplay.enabled = !autoplay.enabled;
+        autoplay.timer.reset();
+        info!("Autoplay enabled: {}", autoplay.enabled);
+    }
+}
+
+fn autoplay(
+    time: Res<Time>,
+    mut autoplay: ResMut<Autoplay>,
+    mut minefield: ResMut<Minefield>,
+    mut puzzle_mode: Option<ResMut<PuzzleMode>>,
+    settings: Res<Settings>,
+    mut actions: EventWriter<MinefieldAction>,
+) {
+    if !autoplay.enabled || !autoplay.waiting && !autoplay.timer.tick(time.delta()).just_finished()
+    {
+        return;
+    }
+
+    let view = BoardView::from(&*minefield);
+    if minefield.state != GameState::Playing {
+        if !autoplay.new_game {
+            autoplay.strategy.end_game(&view);
+            autoplay.new_game = true;
+        }
+        // puzzle mode moves on by itself once it has shown how the puzzle went
+        if puzzle_mode.is_none() {
+            *minefield = settings.minefield();
+        }
+        return;
+    }
+
+    if autoplay.new_game {
+        autoplay.strategy.new_game(&view);
+        autoplay.new_game = false;
+    }
+
+    let Poll::Ready(next) = autoplay.strategy.poll_move(&view) else {
+        autoplay.waiting = true;
+        return;
+    };
+    autoplay.waiting = false;
+    if autoplay.strategy.failed() {
+        warn!("{} failed, stopping autoplay", autoplay.strategy.name());
+        autoplay.enabled = false;
+        return;
+    }
+
+    match next {
+        Some(SolverMove { action, .. }) => match validate_move(&view, action) {
+            Ok(()) => {
+                actions.send(action);
+            }
+            Err(e) => {
+                warn!("{} made an invalid move: {e}", autoplay.strategy.name());
+                autoplay.enabled = false;
+            }
+        },
+        None => {
+            info!("{} gave up, starting a new game", autoplay.strategy.name());
+            autoplay.strategy.end_game(&view);
+            puzzle_mode::new_game(&mut minefield, puzzle_mode.as_deref_mut(), &settings);
+            autoplay.new_game = true;
+        }
+    }
+}
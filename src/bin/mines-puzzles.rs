@@ -0,0 +1,60 @@
+use mines_rs::puzzle::*;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: mines-puzzles [FILE]...
+
+Checks that every puzzle has exactly one logical solution: the numbers match the mines and the
+solver deduces exactly the ? and ! cells. Checks the bundled puzzles if no files are given.";
+
+fn main() -> ExitCode {
+    let files: Vec<String> = std::env::args().skip(1).collect();
+    if files.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let sources = if files.is_empty() {
+        vec![("<bundled>".to_string(), Ok(BUNDLED_PUZZLES.to_string()))]
+    } else {
+        files
+            .into_iter()
+            .map(|file| {
+                let source = std::fs::read_to_string(&file);
+                (file, source)
+            })
+            .collect()
+    };
+
+    let mut failures = 0;
+    for (file, source) in sources {
+        let puzzles = match source
+            .map_err(|e| e.to_string())
+            .and_then(|source| parse_puzzles(&source).map_err(|e| e.to_string()))
+        {
+            Ok(puzzles) => puzzles,
+            Err(e) => {
+                eprintln!("{file}: {e}");
+                failures += 1;
+                continue;
+            }
+        };
+
+        for puzzle in puzzles {
+            match puzzle.check() {
+                Ok(()) => println!("{file}: {}: ok", puzzle.name),
+                Err(e) => {
+                    println!("{file}: {}: {e}", puzzle.name);
+                    failures += 1;
+                }
+            }
+        }
+    }
+
+    if failures == 0 {
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("{failures} puzzle(s) failed");
+        ExitCode::FAILURE
+    }
+}
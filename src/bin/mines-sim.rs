@@ -0,0 +1,248 @@
+use mines_rs::bot_protocol::ProcessStrategy;
+use mines_rs::minefield::FirstClick;
+use mines_rs::simulation::*;
+use mines_rs::strategy::*;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: mines-sim [options]
+
+Plays games headlessly with the built-in strategies and reports how they did.
+
+Options:
+  --size <W>x<H>          board size (default 8x8)
+  --mines <N>             mine count (default 10)
+  --seeds <START>..<END>  seed range, one game per seed (default 0..1000)
+  --first-click <RULE>    what the first reveal finds: anything, safe or opening
+                          (default anything)
+  --strategy <LIST>       comma separated strategies: logic, guess, random (default logic,guess)
+  --bot <COMMAND>         also play an external bot speaking the stdin/stdout protocol,
+                          may be repeated
+  --json                  print the report as JSON instead of a table
+  -h, --help              show this message";
+
+/// The `--first-click` names, which the report uses too.
+const FIRST_CLICKS: [(&str, FirstClick); 3] = [
+    ("anything", FirstClick::Anything),
+    ("safe", FirstClick::Safe),
+    ("opening", FirstClick::Opening),
+];
+
+struct Options {
+    simulation: SimulationOptions,
+    strategies: Vec<String>,
+    bots: Vec<String>,
+    json: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            simulation: SimulationOptions::default(),
+            strategies: vec!["logic".into(), "guess".into()],
+            bots: vec![],
+            json: false,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut strategies: Vec<Box<dyn Strategy>> = options
+        .strategies
+        .iter()
+        .map(|name| builtin_strategy(name, options.simulation.seeds.start).unwrap())
+        .collect();
+    for command in &options.bots {
+        match ProcessStrategy::spawn(command) {
+            Ok(bot) => strategies.push(Box::new(bot)),
+            Err(e) => {
+                eprintln!("error: failed to start bot '{command}': {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let reports: Vec<Report> = strategies
+        .iter_mut()
+        .map(|strategy| simulate(&options.simulation, strategy.as_mut()))
+        .collect();
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+    } else {
+        print_table(&reports);
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} expects a value"));
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--json" => options.json = true,
+            "--size" => {
+                let size = value("--size")?;
+                options.simulation.size = size
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .filter(|&(w, h)| w > 0 && h > 0)
+                    .ok_or(format!("invalid size '{size}', expected <W>x<H>"))?;
+            }
+            "--mines" => {
+                let mines = value("--mines")?;
+                options.simulation.mines = mines
+                    .parse()
+                    .map_err(|e| format!("invalid mine count '{mines}': {e}"))?;
+            }
+            "--seeds" => {
+                let seeds = value("--seeds")?;
+                options.simulation.seeds = seeds
+                    .split_once("..")
+                    .and_then(|(start, end)| Some(start.parse().ok()?..end.parse().ok()?))
+                    .filter(|range| !range.is_empty())
+                    .ok_or(format!(
+                        "invalid seed range '{seeds}', expected <START>..<END>"
+                    ))?;
+            }
+            "--first-click" => {
+                let rule = value("--first-click")?;
+                options.simulation.first_click = FIRST_CLICKS
+                    .iter()
+                    .find(|&&(name, _)| name == rule)
+                    .map(|&(_, first_click)| first_click)
+                    .ok_or(format!(
+                        "unknown first click rule '{rule}', expected anything, safe or opening"
+                    ))?;
+            }
+            "--bot" => options.bots.push(value("--bot")?),
+            "--strategy" => {
+                options.strategies = value("--strategy")?
+                    .split(',')
+                    .map(|name| {
+                        if BUILTIN_STRATEGIES.contains(&name) {
+                            Ok(name.to_string())
+                        } else {
+                            Err(format!("unknown strategy '{name}'"))
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            _ => return Err(format!("unknown argument '{arg}'")),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+fn first_click_name(first_click: FirstClick) -> &'static str {
+    FIRST_CLICKS
+        .iter()
+        .find(|&&(_, rule)| rule == first_click)
+        .map_or("?", |&(name, _)| name)
+}
+
+fn print_table(reports: &[Report]) {
+    println!(
+        "{:<10} {:>9} {:>11} {:>7} {:>7} {:>9} {:>8} {:>8} {:>12}",
+        "strategy",
+        "board",
+        "first click",
+        "games",
+        "wins",
+        "win rate",
+        "avg 3bv",
+        "guesses",
+        "runtime (ms)"
+    );
+    for report in reports {
+        println!(
+            "{:<10} {:>9} {:>11} {:>7} {:>7} {:>8.2}% {:>8.2} {:>8.2} {:>12.2}",
+            report.strategy,
+            format!("{}x{}/{}", report.width, report.height, report.mines),
+            first_click_name(report.first_click),
+            report.games,
+            report.wins,
+            report.win_rate * 100.0,
+            report.average_3bv,
+            report.average_guesses,
+            report.runtime_ms,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse(&[
+            "--size",
+            "16x30",
+            "--mines",
+            "99",
+            "--seeds",
+            "5..10",
+            "--first-click",
+            "opening",
+            "--strategy",
+            "guess",
+            "--bot",
+            "./bot",
+            "--json",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(options.simulation.size, (16, 30));
+        assert_eq!(options.simulation.mines, 99);
+        assert_eq!(options.simulation.seeds, 5..10);
+        assert_eq!(options.simulation.first_click, FirstClick::Opening);
+        assert_eq!(options.strategies, ["guess"]);
+        assert_eq!(options.bots, ["./bot"]);
+        assert!(options.json);
+    }
+
+    #[test]
+    fn help_stops_parsing() {
+        assert!(parse(&["--help", "--bogus"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_bad_seed_ranges() {
+        for seeds in ["10..5", "3..3", "5", "a..b", "..10"] {
+            assert!(parse(&["--seeds", seeds]).is_err(), "{seeds}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert!(parse(&["--size", "0x8"]).is_err());
+        assert!(parse(&["--size", "8"]).is_err());
+        assert!(parse(&["--mines", "-1"]).is_err());
+        assert!(parse(&["--strategy", "logic,cheat"]).is_err());
+        assert!(parse(&["--first-click", "lucky"]).is_err());
+        assert!(parse(&["--mines"]).is_err());
+        assert!(parse(&["--fast"]).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+//! Plays an external bot process over a line based protocol on its stdin/stdout.
+//!
+//! At the start of every game the bot is sent `game <cols> <rows> <mines>`. Before every move it
+//! is sent `board`, one line per row (`#` hidden, `F` flagged, `0`-`8` revealed, `*` mine) and
+//! `end`, and must reply with one of `reveal <row> <col>`, `flag <row> <col>`,
+//! `chord <row> <col>` or `quit`. Appending `guess` to a move counts it as a guess in reports.
+//! When a game ends the bot is sent `result won` or `result lost`.
+//!
+//! Messages are written and replies read on threads of their own, so a slow bot never holds up
+//! the game, not even one that stops reading its stdin. A bot that takes longer than
+//! [`REPLY_TIMEOUT`] to reply (counting from when the board was sent, whether or not it could be
+//! written), or replies with nonsense, is given up on. If the board changes while a reply is on
+//! its way (the player clicked, or a new game started), the reply is dropped when it arrives and
+//! the bot is sent the board again.
+
+use crate::minefield::*;
+use crate::solver::SolverMove;
+use crate::strategy::*;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Mutex;
+use std::task::Poll;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct ProcessStrategy {
+    name: String,
+    child: Child,
+    /// Messages for the bot's stdin, until writing one fails.
+    messages: Sender<String>,
+    /// Lines from the bot's stdout, until it closes it. Only in a mutex to make strategies `Sync`.
+    replies: Mutex<Receiver<io::Result<String>>>,
+    /// The board that was sent for the move that's being waited on, and when.
+    asked: Option<(String, Instant)>,
+    /// Replies still to come to boards that changed before the bot replied, which are dropped.
+    stale: usize,
+    failed: bool,
+}
+
+impl ProcessStrategy {
+    /// Spawns `command`, split on whitespace into a program and its arguments.
+    pub fn spawn(command: &str) -> io::Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "empty bot command",
+        ))?;
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        // ends once the bot closes its stdout, which killing it on drop does
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        // ends once the strategy is dropped or a write fails, leaving the reply to time out
+        let (messages, outgoing) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for message in outgoing {
+                if stdin
+                    .write_all(message.as_bytes())
+                    .and_then(|()| stdin.flush())
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+
+        Ok(Self {
+            name: command.to_string(),
+            child,
+            messages,
+            replies: Mutex::new(replies),
+            asked: None,
+            stale: 0,
+            failed: false,
+        })
+    }
+
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        self.messages
+            .send(message.to_string())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "bot stopped taking messages"))
+    }
+
+    /// Stops waiting on the reply to the last board sent, which is dropped when it arrives.
+    fn forget_asked(&mut self) {
+        if self.asked.take().is_some() {
+            self.stale += 1;
+        }
+    }
+
+    /// The reply to the board in `view`, sending it first unless it already was. Waits for the
+    /// reply if `wait` is set, or is `Pending` until it arrives otherwise.
+    fn request_move(
+        &mut self,
+        view: &BoardView,
+        wait: bool,
+    ) -> Poll<io::Result<Option<SolverMove>>> {
+        let board = encode_board(view);
+        if self
+            .asked
+            .as_ref()
+            .is_some_and(|(asked, _)| *asked != board)
+        {
+            self.forget_asked();
+        }
+        let asked = match self.asked.as_ref().map(|(_, asked)| *asked) {
+            Some(asked) => asked,
+            None => {
+                if let Err(e) = self.send(&board) {
+                    return Poll::Ready(Err(e));
+                }
+                self.asked.insert((board, Instant::now())).1
+            }
+        };
+
+        let reply = loop {
+            let left = REPLY_TIMEOUT.saturating_sub(asked.elapsed());
+            let replies = self.replies.get_mut().unwrap();
+            let reply = if wait {
+                replies.recv_timeout(left)
+            } else {
+                match replies.try_recv() {
+                    Err(TryRecvError::Empty) if !left.is_zero() => return Poll::Pending,
+                    reply => reply.map_err(|e| match e {
+                        TryRecvError::Empty => RecvTimeoutError::Timeout,
+                        TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+                    }),
+                }
+            };
+            match reply {
+                Ok(_) if self.stale > 0 => self.stale -= 1,
+                reply => break reply,
+            }
+        };
+        let line = match reply {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("no reply within {REPLY_TIMEOUT:?}"),
+                )));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "bot closed its stdout",
+                )));
+            }
+        };
+        self.asked = None;
+
+        Poll::Ready(line.and_then(|line| {
+            parse_reply(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }))
+    }
+
+    /// Gives up on the bot for good if it failed to reply.
+    fn check_reply(
+        &mut self,
+        reply: Poll<io::Result<Option<SolverMove>>>,
+    ) -> Poll<Option<SolverMove>> {
+        reply.map(|reply| {
+            reply.unwrap_or_else(|e| {
+                log::warn!(
+                    "{} didn't reply with a move, giving up on it: {e}",
+                    self.name
+                );
+                self.failed = true;
+                None
+            })
+        })
+    }
+}
+
+impl Drop for ProcessStrategy {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Strategy for ProcessStrategy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self, view: &BoardView) {
+        self.forget_asked();
+        let message = format!(
+            "game {} {} {}\n",
+            view.cols(),
+            view.rows(),
+            view.mine_count()
+        );
+        if let Err(e) = self.send(&message) {
+            log::warn!("Failed to start a game with {}: {e}", self.name);
+        }
+    }
+
+    fn next_move(&mut self, view: &BoardView) -> Option<SolverMove> {
+        if self.failed {
+            return None;
+        }
+        let reply = self.request_move(view, true);
+        match self.check_reply(reply) {
+            Poll::Ready(next) => next,
+            Poll::Pending => None,
+        }
+    }
+
+    fn poll_move(&mut self, view: &BoardView) -> Poll<Option<SolverMove>> {
+        if self.failed {
+            return Poll::Ready(None);
+        }
+        let reply = self.request_move(view, false);
+        self.check_reply(reply)
+    }
+
+    fn failed(&self) -> bool {
+        self.failed
+    }
+
+    fn end_game(&mut self, view: &BoardView) {
+        self.forget_asked();
+        let result = match view.state() {
+            GameState::Won => "won",
+            GameState::Lost | GameState::Playing => "lost",
+        };
+        if let Err(e) = self.send(&format!("result {result}\n")) {
+            log::warn!("Failed to send the result to {}: {e}", self.name);
+        }
+    }
+}
+
+fn encode_board(view: &BoardView) -> String {
+    let mut message = String::from("board\n");
+    for row in 0..view.rows() {
+        for col in 0..view.cols() {
+            message.push(match view.get((row, col)) {
+                CellView::Hidden => '#',
+                CellView::Flagged => 'F',
+                CellView::Mine => '*',
+                CellView::Revealed(n) => char::from_digit(n as u32, 10).unwrap_or('?'),
+            });
+        }
+        message.push('\n');
+    }
+    message.push_str("end\n");
+    message
+}
+
+fn parse_reply(line: &str) -> Result<Option<SolverMove>, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let (command, row, col, guess) = match words.as_slice() {
+        ["quit"] => return Ok(None),
+        [command, row, col] => (*command, *row, *col, false),
+        [command, row, col, "guess"] => (*command, *row, *col, true),
+        _ => return Err(format!("malformed reply '{}'", line.trim_end())),
+    };
+
+    let row = row
+        .parse()
+        .map_err(|e| format!("invalid row '{row}': {e}"))?;
+    let col = col
+        .parse()
+        .map_err(|e| format!("invalid column '{col}': {e}"))?;
+    let action = match command {
+        "reveal" => MinefieldAction::Reveal(row, col),
+        "flag" => MinefieldAction::Flag(row, col),
+        "chord" => MinefieldAction::Chord(row, col),
+        _ => return Err(format!("unknown command '{command}'")),
+    };
+
+    Ok(Some(SolverMove { action, guess }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_moves() {
+        assert_eq!(
+            parse_reply("reveal 3 4\n"),
+            Ok(Some(SolverMove {
+                action: MinefieldAction::Reveal(3, 4),
+                guess: false,
+            }))
+        );
+        assert_eq!(
+            parse_reply("  flag 0 1   guess"),
+            Ok(Some(SolverMove {
+                action: MinefieldAction::Flag(0, 1),
+                guess: true,
+            }))
+        );
+        assert_eq!(
+            parse_reply("chord 2 2").map(|reply| reply.map(|reply| reply.action)),
+            Ok(Some(MinefieldAction::Chord(2, 2)))
+        );
+        assert_eq!(parse_reply("quit"), Ok(None));
+    }
+
+    #[test]
+    fn rejects_malformed_replies() {
+        for reply in [
+            "",
+            "reveal",
+            "reveal 1",
+            "reveal 1 2 3",
+            "reveal 1 2 maybe",
+            "reveal -1 2",
+            "reveal 1 two",
+            "dig 1 2",
+            "quit now",
+        ] {
+            assert!(parse_reply(reply).is_err(), "accepted '{reply}'");
+        }
+    }
+
+    #[test]
+    fn encodes_what_the_player_sees() {
+        let mut minefield = Minefield::from_mines(&[vec![true, false, false], vec![true; 3]]);
+        minefield.apply(MinefieldAction::Flag(0, 0));
+        minefield.apply(MinefieldAction::Reveal(0, 1));
+
+        assert_eq!(
+            encode_board(&BoardView::from(&minefield)),
+            "board\nF4#\n###\nend\n"
+        );
+
+        minefield.apply(MinefieldAction::Reveal(1, 1));
+        assert_eq!(
+            encode_board(&BoardView::from(&minefield)),
+            "board\nF4#\n***\nend\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bots_that_stop_reading_dont_hold_up_the_game() {
+        let mut bot = ProcessStrategy::spawn("sleep 10").unwrap();
+        // far more than a pipe holds
+        let minefield = Minefield::from_mines(&vec![vec![false; 1000]; 1000]);
+        let view = BoardView::from(&minefield);
+
+        bot.new_game(&view);
+        assert!(bot.poll_move(&view).is_pending());
+        assert!(bot.poll_move(&view).is_pending());
+        assert!(!bot.failed());
+    }
+}
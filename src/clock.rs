@@ -0,0 +1,66 @@
+//! The game clock in the right-hand counter: time since the first reveal, stopping when the game
+//! ends and while the game is paused.
+
+use crate::commands::*;
+use crate::menu::Screen;
+use crate::minefield::*;
+use bevy::prelude::*;
+use std::time::Duration;
+
+#[derive(Resource, Default, Debug)]
+pub struct GameClock {
+    pub elapsed: Duration,
+}
+
+impl GameClock {
+    /// Whole seconds, as the counter shows them.
+    pub fn seconds(&self) -> usize {
+        self.elapsed.as_secs() as usize
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<GameClock>().add_systems(
+        Update,
+        (tick_clock, update_clock_sprites)
+            .chain()
+            .after(send_minefield_changes),
+    );
+}
+
+pub fn tick_clock(
+    time: Res<Time>,
+    screen: Res<State<Screen>>,
+    minefield: Res<Minefield>,
+    mut clock: ResMut<GameClock>,
+) {
+    if !minefield.started() {
+        if clock.elapsed != Duration::ZERO {
+            clock.elapsed = Duration::ZERO;
+        }
+    } else if minefield.state == GameState::Playing && *screen.get() == Screen::Game {
+        clock.elapsed += time.delta();
+    }
+}
+
+fn update_clock_sprites(
+    clock: Res<GameClock>,
+    mut counters: Query<(&mut ClockCount, &Children)>,
+    mut digits: Query<&mut Sprite>,
+) {
+    let value = clock.seconds();
+    for (mut counter, children) in counters.iter_mut() {
+        if counter.value == value {
+            continue;
+        }
+
+        counter.value = value;
+        for (&child, digit) in children.iter().zip(score_digits(value)) {
+            if let Ok(mut sprite) = digits.get_mut(child)
+                && let Some(texture_atlas) = &mut sprite.texture_atlas
+            {
+                texture_atlas.index = digit;
+            }
+        }
+    }
+}
@@ -1,36 +1,49 @@
-use crate::constants::*;
 use crate::minefield::*;
 use crate::spritesheets::*;
+use crate::tilemap::*;
+use crate::viewport::HUD_LAYER;
 use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
 use bevy::sprite::Anchor;
 
-pub struct SpawnMinefieldSprite {
-    pub index: MinefieldSpriteIndex,
-    pub minefield_data: MinefieldData,
+/// Marks everything spawned for the board, so it can be rebuilt when the layout changes.
+#[derive(Component)]
+pub struct BoardSprite;
+
+/// Spawns the mesh for one [`MinefieldChunk`], with its top-left corner at `position`.
+pub struct SpawnMinefieldChunk {
+    pub origin: (usize, usize),
+    pub size: (usize, usize),
+    pub cell_size: Vec2,
     pub position: Vec2,
 }
 
-impl Command for SpawnMinefieldSprite {
+impl Command for SpawnMinefieldChunk {
     fn apply(self, world: &mut World) {
-        let texture: Handle<Image> = world.load_asset("spritesheet.png");
-        let texture_atlas: &MinefieldSpriteSheet = world.resource();
+        let chunk = MinefieldChunk::new(self.origin, self.size, world.resource::<Minefield>());
+        let sprite_sheet = &world.resource::<MinefieldSpriteSheet>().0;
+        let Some(atlas) = world
+            .resource::<Assets<TextureAtlasLayout>>()
+            .get(sprite_sheet)
+        else {
+            return;
+        };
+        let image = &world.resource::<SpriteSheetImage>().0;
+        let image_size = world
+            .resource::<Assets<Image>>()
+            .get(image)
+            .map_or(atlas.size, |image| image.size())
+            .as_vec2();
+        let mesh = chunk.mesh(self.cell_size, atlas, image_size);
+        let mesh = world.resource_mut::<Assets<Mesh>>().add(mesh);
+        let material = world.resource::<MinefieldMaterial>().0.clone();
 
         world.spawn((
-            Sprite {
-                image: texture.clone(),
-                texture_atlas: Some(TextureAtlas {
-                    layout: texture_atlas.0.clone(),
-                    index: self.index.into(),
-                }),
-                anchor: Anchor::TopLeft,
-                ..default()
-            },
-            Transform::from_xyz(
-                MINEFIELD_OFFSET.0 + self.position.x,
-                MINEFIELD_OFFSET.1 - self.position.y,
-                0.0,
-            ),
-            self.minefield_data,
+            Mesh2d(mesh),
+            MeshMaterial2d(material),
+            Transform::from_translation(self.position.extend(0.0)),
+            chunk,
+            BoardSprite,
         ));
     }
 }
@@ -38,14 +51,18 @@ impl Command for SpawnMinefieldSprite {
 pub struct SpawnBorderSprite {
     pub index: BorderSpriteIndex,
     pub position: Vec2,
+    pub hud: bool,
+    /// Drawn under the field, to fill what it leaves of a tile (see [`crate::layout::Layout::backing_tiles`]).
+    pub behind_field: bool,
 }
 
 impl Command for SpawnBorderSprite {
     fn apply(self, world: &mut World) {
-        let texture: Handle<Image> = world.load_asset("spritesheet.png");
+        let texture: Handle<Image> = world.resource::<SpriteSheetImage>().0.clone();
         let texture_atlas: &BorderSpriteSheet = world.resource();
+        let z = if self.behind_field { -1.0 } else { 0.0 };
 
-        world.spawn((
+        let mut sprite = world.spawn((
             Sprite {
                 image: texture.clone(),
                 texture_atlas: Some(TextureAtlas {
@@ -55,8 +72,12 @@ impl Command for SpawnBorderSprite {
                 anchor: Anchor::TopLeft,
                 ..default()
             },
-            Transform::from_translation(self.position.extend(0.0)),
+            Transform::from_translation(self.position.extend(z)),
+            BoardSprite,
         ));
+        if self.hud {
+            sprite.insert(RenderLayers::layer(HUD_LAYER));
+        }
     }
 }
 
@@ -67,7 +88,7 @@ pub struct SpawnFaceSprite {
 
 impl Command for SpawnFaceSprite {
     fn apply(self, world: &mut World) {
-        let texture: Handle<Image> = world.load_asset("spritesheet.png");
+        let texture: Handle<Image> = world.resource::<SpriteSheetImage>().0.clone();
         let texture_atlas: &FaceSpriteSheet = world.resource();
 
         world.spawn((
@@ -81,6 +102,8 @@ impl Command for SpawnFaceSprite {
                 ..default()
             },
             Transform::from_translation(self.position.extend(1.0)),
+            BoardSprite,
+            RenderLayers::layer(HUD_LAYER),
         ));
     }
 }
@@ -90,101 +113,101 @@ pub struct MineCount {
     pub value: usize,
 }
 
+/// The score sprite for each of the counter's three digits.
+pub fn score_digits(value: usize) -> [usize; 3] {
+    let value = value.min(999);
+    [value / 100, value / 10 % 10, value % 10]
+}
+
 pub struct SpawnMineCount {
     pub position: Vec2,
+    pub digit_width: f32,
+    pub value: usize,
 }
 
 impl Command for SpawnMineCount {
     fn apply(self, world: &mut World) {
-        let texture: Handle<Image> = world.load_asset("spritesheet.png");
-        let texture_atlas: Handle<TextureAtlasLayout> =
-            world.resource::<ScoreSpriteSheet>().0.clone();
+        let counter = spawn_score_digits(world, self.position, self.digit_width, self.value);
+        world
+            .entity_mut(counter)
+            .insert(MineCount { value: self.value });
+    }
+}
+
+/// The seconds on the game clock, shown on the right of the header.
+#[derive(Component)]
+pub struct ClockCount {
+    pub value: usize,
+}
 
+pub struct SpawnClockCount {
+    pub position: Vec2,
+    pub digit_width: f32,
+    pub value: usize,
+}
+
+impl Command for SpawnClockCount {
+    fn apply(self, world: &mut World) {
+        let counter = spawn_score_digits(world, self.position, self.digit_width, self.value);
         world
-            .spawn((
-                MineCount { value: MINE_COUNT },
-                Transform::default(),
-                Visibility::default(),
-            ))
-            .with_children(|parent| {
-                parent.spawn((
-                    Sprite {
-                        image: texture.clone(),
-                        texture_atlas: Some(TextureAtlas {
-                            layout: texture_atlas.clone(),
-                            index: 10,
-                        }),
-                        anchor: Anchor::TopLeft,
-                        ..default()
-                    },
-                    Transform {
-                        translation: Vec3::new(self.position.x, self.position.y, 2.0),
-                        ..default()
-                    },
-                ));
-                parent.spawn((
-                    Sprite {
-                        image: texture.clone(),
-                        texture_atlas: Some(TextureAtlas {
-                            layout: texture_atlas.clone(),
-                            index: 10,
-                        }),
-                        anchor: Anchor::TopLeft,
-                        ..default()
-                    },
-                    Transform {
-                        translation: Vec3::new(
-                            self.position.x + SCORE_SPRITE_SIZE.0,
-                            self.position.y,
-                            2.0,
-                        ),
-                        ..default()
-                    },
-                ));
+            .entity_mut(counter)
+            .insert(ClockCount { value: self.value });
+    }
+}
+
+/// A counter's three digits, as children of the returned entity.
+fn spawn_score_digits(world: &mut World, position: Vec2, digit_width: f32, value: usize) -> Entity {
+    let texture: Handle<Image> = world.resource::<SpriteSheetImage>().0.clone();
+    let texture_atlas: Handle<TextureAtlasLayout> = world.resource::<ScoreSpriteSheet>().0.clone();
+
+    world
+        .spawn((Transform::default(), Visibility::default(), BoardSprite))
+        .with_children(|parent| {
+            for (place, digit) in score_digits(value).into_iter().enumerate() {
                 parent.spawn((
                     Sprite {
                         image: texture.clone(),
                         texture_atlas: Some(TextureAtlas {
                             layout: texture_atlas.clone(),
-                            index: 10,
+                            index: digit,
                         }),
                         anchor: Anchor::TopLeft,
                         ..default()
                     },
+                    RenderLayers::layer(HUD_LAYER),
                     Transform {
                         translation: Vec3::new(
-                            self.position.x + SCORE_SPRITE_SIZE.0 * 2.0,
-                            self.position.y,
+                            position.x + digit_width * place as f32,
+                            position.y,
                             2.0,
                         ),
                         ..default()
                     },
                 ));
-            });
-    }
+            }
+        })
+        .id()
 }
 
 pub struct SpawnScoreFrame {
     pub position: Vec2,
+    pub rect: Rect,
 }
 
 impl Command for SpawnScoreFrame {
     fn apply(self, world: &mut World) {
-        let texture: Handle<Image> = world.load_asset("spritesheet.png");
+        let texture: Handle<Image> = world.resource::<SpriteSheetImage>().0.clone();
 
         world.spawn((
             Sprite {
                 image: texture.clone(),
-                rect: Some(Rect::new(
-                    0.0,
-                    120.0,
-                    SCORE_FRAME_SIZE.0,
-                    120.0 + SCORE_FRAME_SIZE.1,
-                )),
+                rect: Some(self.rect),
                 anchor: Anchor::TopLeft,
                 ..default()
             },
             Transform::from_translation(self.position.extend(1.0)),
+            BoardSprite,
+            RenderLayers::layer(HUD_LAYER),
         ));
     }
 }
@@ -0,0 +1,42 @@
+//! The player's own files, kept as RON in `$XDG_CONFIG_HOME/mines-rs`, or `~/.config/mines-rs`
+//! when that isn't set.
+
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))?;
+    Some(base.join("mines-rs"))
+}
+
+/// Reads `name` from the config directory. Missing files are `None` quietly, broken ones with a
+/// warning, so the defaults get used either way.
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let path = config_dir()?.join(name);
+    let text = std::fs::read_to_string(&path).ok()?;
+    ron::from_str(&text)
+        .inspect_err(|e| warn!("Ignoring {}: {e}", path.display()))
+        .ok()
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    let Some(dir) = config_dir() else {
+        warn!("Nowhere to save {name}, neither XDG_CONFIG_HOME nor HOME is set");
+        return;
+    };
+    let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|text| {
+            std::fs::create_dir_all(&dir)
+                .and_then(|()| std::fs::write(dir.join(name), text))
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        warn!("Failed to save {name}: {e}");
+    }
+}
@@ -0,0 +1,233 @@
+//! The controls menu, opened with F1: every [`Action`] and what it's bound to. Up and down pick
+//! an action, Enter (or clicking it) waits for a new input to add to it, Delete clears it and
+//! Escape backs out. Changes are saved straight away.
+
+use crate::input::*;
+use bevy::prelude::*;
+
+const BACKDROP_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+const ROW_COLOR: Color = Color::NONE;
+const SELECTED_COLOR: Color = Color::srgb(0.25, 0.25, 0.35);
+const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const WARNING_COLOR: Color = Color::srgb(1.0, 0.5, 0.4);
+const FONT_SIZE: f32 = 16.0;
+
+#[derive(Resource, Default, Debug)]
+pub struct ControlsMenu {
+    pub open: bool,
+    selected: usize,
+    /// Whether the next input gets bound to the selected action.
+    capturing: bool,
+    message: String,
+}
+
+impl ControlsMenu {
+    pub fn open(&mut self) {
+        *self = Self {
+            open: true,
+            ..default()
+        };
+    }
+}
+
+#[derive(Component)]
+struct MenuRoot;
+
+/// A row of the menu, for the action at this index of [`Action::ALL`].
+#[derive(Component)]
+struct ActionRow(usize);
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<ControlsMenu>().add_systems(
+        Update,
+        // each step only sees presses from before the one that led to it
+        (
+            capture_binding,
+            navigate_menu,
+            click_rows,
+            open_menu,
+            draw_menu,
+        )
+            .chain(),
+    );
+}
+
+fn open_menu(actions: Res<ButtonInput<Action>>, mut menu: ResMut<ControlsMenu>) {
+    if actions.just_pressed(Action::Controls) {
+        menu.open();
+    }
+}
+
+/// Runs on raw input, since the menu has the actions to itself while it's open.
+fn navigate_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    raw: RawInput,
+    mut bindings: ResMut<Bindings>,
+    mut menu: ResMut<ControlsMenu>,
+) {
+    if !menu.open || menu.capturing {
+        return;
+    }
+
+    let closing = keys.just_pressed(KeyCode::Escape)
+        || bindings
+            .get(Action::Controls)
+            .iter()
+            .any(|&binding| raw.just_pressed(binding));
+    if closing {
+        menu.open = false;
+        return;
+    }
+
+    let count = Action::ALL.len();
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        menu.selected = (menu.selected + 1) % count;
+    }
+    if keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) {
+        menu.capturing = true;
+        menu.message.clear();
+    }
+    if keys.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        let action = Action::ALL[menu.selected];
+        bindings.0.insert(action, vec![]);
+        bindings.save();
+        menu.message = format!("Cleared {}", action.name());
+    }
+}
+
+fn click_rows(
+    rows: Query<(&Interaction, &ActionRow), Changed<Interaction>>,
+    mut menu: ResMut<ControlsMenu>,
+) {
+    if !menu.open || menu.capturing {
+        return;
+    }
+
+    for (interaction, row) in rows.iter() {
+        if *interaction == Interaction::Pressed {
+            menu.selected = row.0;
+            menu.capturing = true;
+            menu.message.clear();
+        }
+    }
+}
+
+/// Binds the first input pressed after picking an action, unless another action already has it.
+fn capture_binding(
+    keys: Res<ButtonInput<KeyCode>>,
+    raw: RawInput,
+    mut bindings: ResMut<Bindings>,
+    mut menu: ResMut<ControlsMenu>,
+) {
+    if !menu.capturing {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        menu.capturing = false;
+        return;
+    }
+    let Some(binding) = raw.first_just_pressed() else {
+        return;
+    };
+
+    let action = Action::ALL[menu.selected];
+    menu.capturing = false;
+    menu.message = match bindings.action_for(binding) {
+        Some(other) => format!("{binding} is already bound to {}", other.name()),
+        None => {
+            bindings.0.entry(action).or_default().push(binding);
+            bindings.save();
+            String::new()
+        }
+    };
+}
+
+fn draw_menu(
+    mut commands: Commands,
+    menu: Res<ControlsMenu>,
+    bindings: Res<Bindings>,
+    roots: Query<Entity, With<MenuRoot>>,
+) {
+    if !menu.is_changed() && !bindings.is_changed() {
+        return;
+    }
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+    if !menu.open {
+        return;
+    }
+
+    let text = |text: String, color: Color| {
+        (
+            Text::new(text),
+            TextFont {
+                font_size: FONT_SIZE,
+                ..default()
+            },
+            TextColor(color),
+        )
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(2.0),
+                ..default()
+            },
+            BackgroundColor(BACKDROP_COLOR),
+            // over the title and pause screens
+            GlobalZIndex(2),
+            MenuRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn(text("Controls".into(), TEXT_COLOR));
+
+            for (index, action) in Action::ALL.into_iter().enumerate() {
+                let selected = index == menu.selected;
+                let bound = if selected && menu.capturing {
+                    "Press an input to add, or Esc to cancel".to_string()
+                } else {
+                    let bound: Vec<String> = bindings
+                        .get(action)
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect();
+                    bound.join(", ")
+                };
+
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(560.0),
+                            justify_content: JustifyContent::SpaceBetween,
+                            column_gap: Val::Px(16.0),
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(if selected { SELECTED_COLOR } else { ROW_COLOR }),
+                        ActionRow(index),
+                    ))
+                    .with_children(|row| {
+                        row.spawn(text(action.name().into(), TEXT_COLOR));
+                        row.spawn(text(bound, TEXT_COLOR));
+                    });
+            }
+
+            parent.spawn(text(menu.message.clone(), WARNING_COLOR));
+            parent.spawn(text(
+                "Up/Down: select   Enter: add input   Delete: clear   Esc: close".into(),
+                TEXT_COLOR,
+            ));
+        });
+}
@@ -0,0 +1,106 @@
+//! Flag mode, for touchpads and one-button devices: the primary click (or a tap) flags hidden
+//! cells and chords opened ones, and the secondary click (or a long press) reveals. Toggled with
+//! [`Action::ToggleFlagMode`] (G) or the flag button in the corner.
+
+use crate::input::Action;
+use crate::spritesheets::*;
+use bevy::prelude::*;
+
+const BUTTON_SIZE: f32 = 40.0;
+const BUTTON_MARGIN: f32 = 8.0;
+const OFF_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.3);
+const ON_COLOR: Color = Color::srgb(0.9, 0.75, 0.2);
+
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct FlagMode(pub bool);
+
+impl FlagMode {
+    /// The actions pointers reveal and flag with, in that order.
+    pub fn actions(&self) -> (Action, Action) {
+        if self.0 {
+            (Action::Flag, Action::Reveal)
+        } else {
+            (Action::Reveal, Action::Flag)
+        }
+    }
+}
+
+#[derive(Component)]
+struct FlagModeButton;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<FlagMode>()
+        .add_systems(Startup, spawn_button)
+        .add_systems(Update, (toggle_flag_mode, update_button).chain());
+}
+
+fn spawn_button(
+    mut commands: Commands,
+    image: Res<SpriteSheetImage>,
+    sprite_sheet: Res<MinefieldSpriteSheet>,
+) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(BUTTON_MARGIN),
+                bottom: Val::Px(BUTTON_MARGIN),
+                width: Val::Px(BUTTON_SIZE),
+                height: Val::Px(BUTTON_SIZE),
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(OFF_COLOR),
+            FlagModeButton,
+        ))
+        .with_child((
+            UiImage::from_atlas_image(
+                image.0.clone(),
+                TextureAtlas {
+                    layout: sprite_sheet.0.clone(),
+                    index: MinefieldSpriteIndex::Flag.into(),
+                },
+            ),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+        ));
+}
+
+fn toggle_flag_mode(
+    actions: Res<ButtonInput<Action>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<FlagModeButton>)>,
+    mut flag_mode: ResMut<FlagMode>,
+) {
+    let clicked = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if actions.just_pressed(Action::ToggleFlagMode) || clicked {
+        flag_mode.0 = !flag_mode.0;
+        info!("Flag mode: {}", flag_mode.0);
+    }
+}
+
+fn update_button(
+    flag_mode: Res<FlagMode>,
+    image: Res<SpriteSheetImage>,
+    mut buttons: Query<(&mut BackgroundColor, &Children), With<FlagModeButton>>,
+    mut icons: Query<&mut UiImage>,
+) {
+    if !flag_mode.is_changed() && !image.is_changed() {
+        return;
+    }
+
+    for (mut background, children) in buttons.iter_mut() {
+        background.0 = if flag_mode.0 { ON_COLOR } else { OFF_COLOR };
+        for &child in children {
+            // the theme can swap the spritesheet out from under it
+            if let Ok(mut icon) = icons.get_mut(child) {
+                icon.image = image.0.clone();
+            }
+        }
+    }
+}
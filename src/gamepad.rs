@@ -0,0 +1,117 @@
+//! Playing with a controller, through the same [`CellCursor`] as the keyboard. The D-pad or left
+//! stick moves it one cell per tap and speeds up while held. The buttons are bindings like any
+//! other (see [`crate::input`]): by default A reveals, B flags, X chords and the right shoulder
+//! button starts a new game. Controllers can be plugged in and out at any time.
+
+use crate::input::InputCaptured;
+use crate::keyboard::{self, CellCursor};
+use crate::minefield::*;
+use crate::pointer::HoveredCell;
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::prelude::*;
+
+/// How far the stick has to be pushed along an axis to move that way.
+const STICK_THRESHOLD: f32 = 0.5;
+/// Seconds a direction is held before the cursor starts gliding.
+const GLIDE_DELAY: f32 = 0.3;
+/// Cells per second when the cursor starts gliding, and once it's at full speed.
+const GLIDE_SPEED: (f32, f32) = (8.0, 40.0);
+/// Seconds of gliding to reach full speed.
+const GLIDE_ACCELERATION: f32 = 1.0;
+
+/// The direction being held and for how long, so held directions can glide faster and faster.
+#[derive(Default)]
+struct Glide {
+    direction: (isize, isize),
+    held_for: f32,
+    /// Cells covered but not moved yet.
+    distance: f32,
+}
+
+pub fn plugin(app: &mut App) {
+    // after the keyboard's, so a button that brings the cursor up doesn't act yet
+    app.add_systems(
+        Update,
+        (
+            log_connections,
+            control_cursor.after(keyboard::control_cursor),
+        ),
+    );
+}
+
+fn log_connections(mut connections: EventReader<GamepadConnectionEvent>) {
+    for event in connections.read() {
+        match &event.connection {
+            GamepadConnection::Connected { name, .. } => info!("Gamepad connected: {name}"),
+            GamepadConnection::Disconnected => info!("Gamepad disconnected"),
+        }
+    }
+}
+
+/// `(rows, cols)` for a stick or D-pad position, with up being positive `y`.
+fn direction(stick: Vec2) -> (isize, isize) {
+    let axis = |value: f32| {
+        if value >= STICK_THRESHOLD {
+            1
+        } else if value <= -STICK_THRESHOLD {
+            -1
+        } else {
+            0
+        }
+    };
+    (-axis(stick.y), axis(stick.x))
+}
+
+fn control_cursor(
+    time: Res<Time>,
+    gamepads: Query<&Gamepad>,
+    captured: Res<InputCaptured>,
+    hovered: Res<HoveredCell>,
+    minefield: Res<Minefield>,
+    mut cursor: ResMut<CellCursor>,
+    mut glide: Local<Glide>,
+) {
+    if captured.0 {
+        return;
+    }
+
+    // every controller drives the same cursor, and one going away just stops counting
+    let held = gamepads
+        .iter()
+        .map(|gamepad| direction(gamepad.dpad() + gamepad.left_stick()))
+        .find(|&direction| direction != (0, 0))
+        .unwrap_or_default();
+
+    let mut step = (0, 0);
+    if held != glide.direction {
+        *glide = Glide {
+            direction: held,
+            ..default()
+        };
+        step = held;
+    } else if held != (0, 0) {
+        glide.held_for += time.delta_secs();
+        let gliding = glide.held_for - GLIDE_DELAY;
+        if gliding > 0.0 {
+            let speed = GLIDE_SPEED
+                .0
+                .lerp(GLIDE_SPEED.1, (gliding / GLIDE_ACCELERATION).min(1.0));
+            glide.distance += speed * time.delta_secs();
+            let cells = glide.distance.floor();
+            glide.distance -= cells;
+            step = (held.0 * cells as isize, held.1 * cells as isize);
+        }
+    }
+
+    let pressed = gamepads
+        .iter()
+        .any(|gamepad| gamepad.digital.get_just_pressed().next().is_some());
+    if (step == (0, 0) && !pressed) || !cursor.activate(hovered.0) {
+        return;
+    }
+
+    let position = cursor.stepped(step, &minefield);
+    if cursor.position != position {
+        cursor.position = position;
+    }
+}
@@ -0,0 +1,93 @@
+//! Hints: [`Action::Hint`] (`/` by default) highlights a cell that can be worked out from what's
+//! showing, the move the [`crate::solver`] would make next without guessing. The highlight goes
+//! away as soon as the board changes.
+
+use crate::input::Action;
+use crate::layout::Layout;
+use crate::minefield::*;
+use crate::solver::*;
+use crate::strategy::BoardView;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+const HINT_COLOR: Color = Color::srgba(0.2, 0.9, 0.4, 0.45);
+
+/// The hinted move, until the board changes.
+#[derive(Resource, Default, Debug)]
+pub struct Hint(pub Option<MinefieldAction>);
+
+#[derive(Component)]
+struct HintSprite;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Hint>()
+        .add_systems(Startup, spawn_hint)
+        .add_systems(
+            Update,
+            (
+                find_hint.after(send_minefield_changes),
+                move_hint_sprite.run_if(resource_exists::<Layout>),
+            )
+                .chain(),
+        );
+}
+
+fn spawn_hint(mut commands: Commands) {
+    commands.spawn((
+        Sprite {
+            color: HINT_COLOR,
+            anchor: Anchor::TopLeft,
+            ..default()
+        },
+        Transform::default(),
+        Visibility::Hidden,
+        HintSprite,
+    ));
+}
+
+fn find_hint(
+    actions: Res<ButtonInput<Action>>,
+    mut changes: EventReader<MinefieldChange>,
+    minefield: Res<Minefield>,
+    mut hint: ResMut<Hint>,
+) {
+    if changes.read().count() > 0 && hint.0.is_some() {
+        hint.0 = None;
+    }
+    if !actions.just_pressed(Action::Hint) {
+        return;
+    }
+
+    hint.0 = next_move(&BoardView::from(&*minefield), SolverStrategy::Logic).map(|hint| {
+        info!("Hint: {:?}", hint.action);
+        hint.action
+    });
+    if hint.0.is_none() && minefield.state == GameState::Playing {
+        info!("Nothing more can be worked out, it's down to a guess");
+    }
+}
+
+fn move_hint_sprite(
+    hint: Res<Hint>,
+    layout: Res<Layout>,
+    mut sprite: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<HintSprite>>,
+) {
+    if !hint.is_changed() && !layout.is_changed() {
+        return;
+    }
+
+    for (mut sprite, mut transform, mut visibility) in sprite.iter_mut() {
+        match hint.0 {
+            Some(
+                MinefieldAction::Reveal(row, col)
+                | MinefieldAction::Flag(row, col)
+                | MinefieldAction::Chord(row, col),
+            ) => {
+                sprite.custom_size = Some(layout.sprites.field.size());
+                transform.translation = layout.cell_position((row, col)).extend(0.55);
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
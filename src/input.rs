@@ -0,0 +1,388 @@
+//! Maps raw keys and buttons onto game [`Action`]s, so systems ask whether the player wants to
+//! reveal rather than whether the left mouse button went up. Bindings load from `controls.ron`
+//! in the config directory (see [`crate::config`]) and can be changed in the controls menu.
+
+use crate::config;
+use bevy::ecs::system::SystemParam;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const BINDINGS_FILE: &str = "controls.ron";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    /// Reveal a hidden cell, or chord an opened one.
+    Reveal,
+    Flag,
+    Chord,
+    NewGame,
+    /// Takes back the last move of the game in progress.
+    Undo,
+    /// Highlights a cell that can be worked out from what's showing (see [`crate::hint`]).
+    Hint,
+    /// Opens the pause menu, which is where the game can be quit from.
+    Quit,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    ZoomIn,
+    ZoomOut,
+    ZoomToFit,
+    /// Held to make the cursor actions pan the board rather than move the keyboard cursor.
+    Pan,
+    /// Drags the board around while held.
+    Drag,
+    /// Held to make reveal drag the board, for mice without a middle button.
+    RevealDrags,
+    ToggleFlagMode,
+    ToggleAutoplay,
+    NextTheme,
+    Controls,
+    Settings,
+}
+
+impl Action {
+    /// Every action, in the order the controls menu lists them.
+    pub const ALL: [Action; 22] = [
+        Action::Reveal,
+        Action::Flag,
+        Action::Chord,
+        Action::NewGame,
+        Action::Undo,
+        Action::Hint,
+        Action::Quit,
+        Action::CursorUp,
+        Action::CursorDown,
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomToFit,
+        Action::Pan,
+        Action::Drag,
+        Action::RevealDrags,
+        Action::ToggleFlagMode,
+        Action::ToggleAutoplay,
+        Action::NextTheme,
+        Action::Controls,
+        Action::Settings,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Reveal => "Reveal",
+            Action::Flag => "Flag",
+            Action::Chord => "Chord",
+            Action::NewGame => "New game",
+            Action::Undo => "Undo",
+            Action::Hint => "Hint",
+            Action::Quit => "Pause",
+            Action::CursorUp => "Cursor up",
+            Action::CursorDown => "Cursor down",
+            Action::CursorLeft => "Cursor left",
+            Action::CursorRight => "Cursor right",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::ZoomToFit => "Zoom to fit",
+            Action::Pan => "Pan (hold)",
+            Action::Drag => "Drag",
+            Action::RevealDrags => "Reveal drags (hold)",
+            Action::ToggleFlagMode => "Toggle flag mode",
+            Action::ToggleAutoplay => "Toggle autoplay",
+            Action::NextTheme => "Next theme",
+            Action::Controls => "Controls",
+            Action::Settings => "Settings",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// A button on any connected gamepad.
+    Gamepad(GamepadButton),
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                let name = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                write!(f, "{name}")
+            }
+            Binding::Mouse(button) => write!(f, "Mouse {button:?}"),
+            Binding::Gamepad(button) => write!(f, "Pad {button:?}"),
+        }
+    }
+}
+
+/// Which inputs trigger each action. Several can share an action, but an input only triggers
+/// one, apart from the middle button, which chords on a click and drags the board by default.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bindings(pub HashMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Binding::*;
+
+        Self(HashMap::from_iter([
+            (
+                Action::Reveal,
+                vec![
+                    Mouse(MouseButton::Left),
+                    Key(KeyCode::Space),
+                    Key(KeyCode::Enter),
+                    Key(KeyCode::NumpadEnter),
+                    Gamepad(GamepadButton::South),
+                ],
+            ),
+            (
+                Action::Flag,
+                vec![
+                    Mouse(MouseButton::Right),
+                    Key(KeyCode::KeyF),
+                    Gamepad(GamepadButton::East),
+                ],
+            ),
+            (
+                Action::Chord,
+                vec![
+                    Mouse(MouseButton::Middle),
+                    Key(KeyCode::KeyC),
+                    Gamepad(GamepadButton::West),
+                ],
+            ),
+            (
+                Action::NewGame,
+                vec![Key(KeyCode::KeyN), Gamepad(GamepadButton::RightTrigger)],
+            ),
+            (
+                Action::Undo,
+                vec![Key(KeyCode::KeyZ), Gamepad(GamepadButton::LeftTrigger)],
+            ),
+            (
+                Action::Hint,
+                vec![Key(KeyCode::Slash), Gamepad(GamepadButton::North)],
+            ),
+            (
+                Action::Quit,
+                vec![Key(KeyCode::Escape), Gamepad(GamepadButton::Start)],
+            ),
+            (
+                Action::CursorUp,
+                vec![
+                    Key(KeyCode::ArrowUp),
+                    Key(KeyCode::KeyW),
+                    Key(KeyCode::KeyK),
+                ],
+            ),
+            (
+                Action::CursorDown,
+                vec![
+                    Key(KeyCode::ArrowDown),
+                    Key(KeyCode::KeyS),
+                    Key(KeyCode::KeyJ),
+                ],
+            ),
+            (
+                Action::CursorLeft,
+                vec![
+                    Key(KeyCode::ArrowLeft),
+                    Key(KeyCode::KeyA),
+                    Key(KeyCode::KeyH),
+                ],
+            ),
+            (
+                Action::CursorRight,
+                vec![
+                    Key(KeyCode::ArrowRight),
+                    Key(KeyCode::KeyD),
+                    Key(KeyCode::KeyL),
+                ],
+            ),
+            (
+                Action::ZoomIn,
+                vec![Key(KeyCode::Equal), Key(KeyCode::NumpadAdd)],
+            ),
+            (
+                Action::ZoomOut,
+                vec![Key(KeyCode::Minus), Key(KeyCode::NumpadSubtract)],
+            ),
+            (Action::ZoomToFit, vec![Key(KeyCode::Digit0)]),
+            (
+                Action::Pan,
+                vec![Key(KeyCode::ShiftLeft), Key(KeyCode::ShiftRight)],
+            ),
+            (Action::Drag, vec![Mouse(MouseButton::Middle)]),
+            // not space, which reveals the keyboard cursor's cell (see `crate::keyboard`)
+            (
+                Action::RevealDrags,
+                vec![Key(KeyCode::AltLeft), Key(KeyCode::AltRight)],
+            ),
+            (Action::ToggleFlagMode, vec![Key(KeyCode::KeyG)]),
+            (Action::ToggleAutoplay, vec![Key(KeyCode::KeyB)]),
+            (Action::NextTheme, vec![Key(KeyCode::KeyT)]),
+            (Action::Controls, vec![Key(KeyCode::F1)]),
+            (Action::Settings, vec![Key(KeyCode::F2)]),
+        ]))
+    }
+}
+
+impl Bindings {
+    /// The user's bindings, with the defaults for any action they don't mention.
+    pub fn load() -> Self {
+        let mut bindings = Self::default();
+        if let Some(Bindings(saved)) = config::load(BINDINGS_FILE) {
+            bindings.0.extend(saved);
+        }
+        bindings
+    }
+
+    pub fn save(&self) {
+        config::save(BINDINGS_FILE, self);
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// The action an input is bound to, if any.
+    pub fn action_for(&self, binding: Binding) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&action| self.get(action).contains(&binding))
+    }
+}
+
+/// Kept in step with the settings screen (see [`crate::settings::Settings::swap_buttons`]).
+#[derive(Resource, Debug, Default)]
+pub struct MouseSettings {
+    /// Swaps the left and right buttons, so bindings mean primary and secondary rather than
+    /// left and right.
+    pub swap_buttons: bool,
+}
+
+impl MouseSettings {
+    /// The button a binding means by a physical button, or the other way around.
+    pub fn map(&self, button: MouseButton) -> MouseButton {
+        match button {
+            MouseButton::Left if self.swap_buttons => MouseButton::Right,
+            MouseButton::Right if self.swap_buttons => MouseButton::Left,
+            button => button,
+        }
+    }
+}
+
+/// Set while a menu has the input, so none of it reaches the game.
+#[derive(Resource, Default, Debug)]
+pub struct InputCaptured(pub bool);
+
+pub fn plugin(app: &mut App) {
+    app.insert_resource(Bindings::load())
+        .init_resource::<MouseSettings>()
+        .init_resource::<InputCaptured>()
+        .init_resource::<ButtonInput<Action>>()
+        .add_systems(PreUpdate, update_actions.after(InputSystem));
+}
+
+/// The keyboard, mouse and gamepads as [`Binding`]s.
+#[derive(SystemParam)]
+pub struct RawInput<'w, 's> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse_buttons: Res<'w, ButtonInput<MouseButton>>,
+    mouse_settings: Res<'w, MouseSettings>,
+    gamepads: Query<'w, 's, &'static Gamepad>,
+}
+
+impl RawInput<'_, '_> {
+    pub fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.pressed(key),
+            Binding::Mouse(button) => self.mouse_buttons.pressed(self.mouse_settings.map(button)),
+            Binding::Gamepad(button) => self
+                .gamepads
+                .iter()
+                .any(|gamepad| gamepad.digital.pressed(button)),
+        }
+    }
+
+    pub fn just_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.just_pressed(key),
+            Binding::Mouse(button) => self
+                .mouse_buttons
+                .just_pressed(self.mouse_settings.map(button)),
+            Binding::Gamepad(button) => self
+                .gamepads
+                .iter()
+                .any(|gamepad| gamepad.digital.just_pressed(button)),
+        }
+    }
+
+    /// Something pressed this frame, for binding to an action.
+    pub fn first_just_pressed(&self) -> Option<Binding> {
+        let key = self
+            .keys
+            .get_just_pressed()
+            .next()
+            .copied()
+            .map(Binding::Key);
+        let mouse = || {
+            self.mouse_buttons
+                .get_just_pressed()
+                .next()
+                .map(|&button| Binding::Mouse(self.mouse_settings.map(button)))
+        };
+        let gamepad = || {
+            self.gamepads.iter().find_map(|gamepad| {
+                gamepad
+                    .digital
+                    .get_just_pressed()
+                    .next()
+                    .copied()
+                    .map(Binding::Gamepad)
+            })
+        };
+        key.or_else(mouse).or_else(gamepad)
+    }
+}
+
+pub fn update_actions(
+    bindings: Res<Bindings>,
+    captured: Res<InputCaptured>,
+    raw: RawInput,
+    mut actions: ResMut<ButtonInput<Action>>,
+    // held when a menu took the input, and ignored until they're let go
+    mut blocked: Local<HashSet<Action>>,
+) {
+    actions.clear();
+    for action in Action::ALL {
+        let pressed = bindings
+            .get(action)
+            .iter()
+            .any(|&binding| raw.pressed(binding));
+
+        if captured.0 || blocked.contains(&action) {
+            if pressed {
+                blocked.insert(action);
+            } else {
+                blocked.remove(&action);
+            }
+            // without sending a release, so nothing acts on it
+            actions.reset(action);
+        } else if pressed {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+}
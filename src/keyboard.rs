@@ -0,0 +1,187 @@
+//! Playing without a mouse: a cursor over the field moved with the cursor [`Action`]s (the arrow
+//! keys, WASD or HJKL by default), which reveal, flag and chord then act on. The cursor shows up
+//! on the first move and hides again when the mouse moves, handing those actions back to it.
+//!
+//! Reveal is Space or Enter, flag is F and chord is C. Chord would be D, but D already moves the
+//! cursor right as part of WASD, so it's on C instead. D can still be given to chord in the
+//! controls screen once it's cleared from cursor right.
+
+use crate::input::Action;
+use crate::layout::Layout;
+use crate::minefield::*;
+use crate::pointer::HoveredCell;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::window::CursorMoved;
+
+const CURSOR_COLOR: Color = Color::srgba(1.0, 0.85, 0.0, 0.45);
+/// Seconds a movement key is held before it starts repeating.
+const REPEAT_DELAY: f32 = 0.25;
+/// Seconds between moves once repeating.
+const REPEAT_INTERVAL: f32 = 0.05;
+
+const MOVES: [(Action, (isize, isize)); 4] = [
+    (Action::CursorUp, (-1, 0)),
+    (Action::CursorDown, (1, 0)),
+    (Action::CursorLeft, (0, -1)),
+    (Action::CursorRight, (0, 1)),
+];
+
+/// The selected cell when playing with the keyboard or a gamepad.
+#[derive(Resource, Default, Debug)]
+pub struct CellCursor {
+    /// `(row, col)` of the selected cell.
+    pub position: (usize, usize),
+    /// Whether the cursor is in use, rather than the mouse.
+    pub active: bool,
+}
+
+impl CellCursor {
+    /// Shows the cursor, starting from wherever the mouse was. Returns whether it was already
+    /// showing, so the input that brought it up doesn't also act on a cell the player can't see.
+    pub fn activate(&mut self, hovered: Option<(usize, usize)>) -> bool {
+        if self.active {
+            return true;
+        }
+        self.active = true;
+        if let Some(position) = hovered {
+            self.position = position;
+        }
+        false
+    }
+
+    /// Where moving by `(rows, cols)` ends up, staying on the field.
+    pub fn stepped(&self, by: (isize, isize), minefield: &Minefield) -> (usize, usize) {
+        let (row, col) = self.position;
+        (
+            row.saturating_add_signed(by.0)
+                .min(minefield.rows().saturating_sub(1)),
+            col.saturating_add_signed(by.1)
+                .min(minefield.cols().saturating_sub(1)),
+        )
+    }
+
+    /// What a reveal, flag or chord does at the cursor.
+    pub fn action(&self, minefield: &Minefield, action: Action) -> Option<MinefieldAction> {
+        let (row, col) = self.position;
+        match action {
+            Action::Reveal if minefield.hidden[row][col] => Some(MinefieldAction::Reveal(row, col)),
+            Action::Reveal | Action::Chord => Some(MinefieldAction::Chord(row, col)),
+            Action::Flag => Some(MinefieldAction::Flag(row, col)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct CursorSprite;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<CellCursor>()
+        .add_systems(Startup, spawn_cursor)
+        .add_systems(
+            Update,
+            (
+                control_cursor,
+                move_cursor_sprite.run_if(resource_exists::<Layout>),
+            )
+                .chain(),
+        );
+}
+
+fn spawn_cursor(mut commands: Commands) {
+    commands.spawn((
+        Sprite {
+            color: CURSOR_COLOR,
+            anchor: Anchor::TopLeft,
+            ..default()
+        },
+        Transform::default(),
+        Visibility::Hidden,
+        CursorSprite,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn control_cursor(
+    time: Res<Time>,
+    actions: Res<ButtonInput<Action>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    hovered: Res<HoveredCell>,
+    minefield: Res<Minefield>,
+    mut cursor: ResMut<CellCursor>,
+    mut minefield_actions: EventWriter<MinefieldAction>,
+    mut repeat_in: Local<f32>,
+) {
+    if cursor_moved.read().count() > 0 || mouse_buttons.get_just_pressed().next().is_some() {
+        cursor.active = false;
+    }
+    if minefield.rows() == 0 || minefield.cols() == 0 {
+        return;
+    }
+
+    let mut moves: Vec<_> = MOVES
+        .into_iter()
+        .filter(|&(action, _)| actions.just_pressed(action))
+        .collect();
+    if !moves.is_empty() {
+        *repeat_in = REPEAT_DELAY;
+    } else if MOVES.iter().any(|&(action, _)| actions.pressed(action)) {
+        *repeat_in -= time.delta_secs();
+        if *repeat_in <= 0.0 {
+            *repeat_in += REPEAT_INTERVAL;
+            moves = MOVES
+                .into_iter()
+                .filter(|&(action, _)| actions.pressed(action))
+                .collect();
+        }
+    }
+    // they pan the camera instead (see `crate::viewport`)
+    if actions.pressed(Action::Pan) {
+        moves.clear();
+    }
+    let step = moves
+        .into_iter()
+        .fold((0, 0), |(rows, cols), (_, by)| (rows + by.0, cols + by.1));
+
+    if step != (0, 0) && !cursor.activate(hovered.0) {
+        return;
+    }
+    if !cursor.active {
+        return;
+    }
+
+    // also keeps it on the field after the board shrinks
+    let position = cursor.stepped(step, &minefield);
+    if cursor.position != position {
+        cursor.position = position;
+    }
+    for action in [Action::Reveal, Action::Flag, Action::Chord] {
+        if actions.just_pressed(action)
+            && let Some(minefield_action) = cursor.action(&minefield, action)
+        {
+            minefield_actions.send(minefield_action);
+        }
+    }
+}
+
+fn move_cursor_sprite(
+    cursor: Res<CellCursor>,
+    layout: Res<Layout>,
+    mut sprite: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<CursorSprite>>,
+) {
+    if !cursor.is_changed() && !layout.is_changed() {
+        return;
+    }
+
+    for (mut sprite, mut transform, mut visibility) in sprite.iter_mut() {
+        if cursor.active {
+            sprite.custom_size = Some(layout.sprites.field.size());
+            transform.translation = layout.cell_position(cursor.position).extend(0.6);
+            *visibility = Visibility::Visible;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
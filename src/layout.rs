@@ -0,0 +1,496 @@
+use crate::commands::*;
+use crate::constants::*;
+use crate::minefield::*;
+use crate::settings::Settings;
+use crate::spritesheets::*;
+use crate::tilemap::chunks;
+use bevy::prelude::*;
+
+/// Where everything goes for a given board size, in world units with the origin at the
+/// bottom-left of the window. Every position is the top-left corner of its sprite. Like the
+/// [`SpriteSheetLayout`] it's worked out from, only there once a theme has loaded.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Layout {
+    /// `(width, height)` in cells.
+    pub board_size: (usize, usize),
+    pub size: Vec2,
+    pub minefield_offset: Vec2,
+    pub sprites: SpriteSheetLayout,
+    header_rows: usize,
+    inner_cols: usize,
+    field_cols: (usize, usize),
+    field_rows: usize,
+}
+
+impl Layout {
+    pub fn new(board_size: (usize, usize), sprites: &SpriteSheetLayout) -> Self {
+        let border = sprites.border.size();
+        let field = Vec2::new(board_size.0 as f32, board_size.1 as f32) * sprites.field.size();
+
+        // the header fits the face and a score frame (inset by a pixel) on either side of it
+        let (face, score_frame) = (sprites.face.size(), sprites.score_frame.size());
+        let header = Vec2::new(
+            2.0 * (score_frame.x + 1.0) + face.x,
+            face.y.max(score_frame.y + 2.0),
+        );
+        let header_rows = (header.y / border.y).ceil() as usize;
+
+        let inner_cols = (field.x.max(header.x) / border.x).ceil() as usize;
+        let field_width_cols = (field.x / border.x).ceil() as usize;
+        let field_left_col = (inner_cols - field_width_cols) / 2;
+        let field_rows = (field.y / border.y).ceil() as usize;
+
+        let size = Vec2::new(
+            border.x * (inner_cols as f32 + 2.0),
+            border.y * ((header_rows + field_rows) as f32 + 3.0),
+        );
+
+        Self {
+            board_size,
+            size,
+            minefield_offset: Vec2::new(
+                border.x * (field_left_col as f32 + 1.0),
+                size.y - border.y * (header_rows as f32 + 2.0),
+            ),
+            sprites: sprites.clone(),
+            header_rows,
+            inner_cols,
+            field_cols: (field_left_col, field_left_col + field_width_cols),
+            field_rows,
+        }
+    }
+
+    pub fn border_tiles(&self) -> Vec<(BorderSpriteIndex, Vec2)> {
+        let border = self.sprites.border.size();
+        let right = self.size.x - border.x;
+        let top = self.size.y;
+        let join = top - border.y * (self.header_rows as f32 + 1.0);
+
+        let mut tiles = vec![
+            (BorderSpriteIndex::TopLeftCorner, Vec2::new(0.0, top)),
+            (BorderSpriteIndex::TopRightCorner, Vec2::new(right, top)),
+            (
+                BorderSpriteIndex::BottomLeftCorner,
+                Vec2::new(0.0, border.y),
+            ),
+            (
+                BorderSpriteIndex::BottomRightCorner,
+                Vec2::new(right, border.y),
+            ),
+            (BorderSpriteIndex::JoinVerticalLeft, Vec2::new(0.0, join)),
+            (BorderSpriteIndex::JoinVerticalRight, Vec2::new(right, join)),
+        ];
+
+        for col in 0..self.inner_cols {
+            let x = border.x * (col as f32 + 1.0);
+            for y in [top, join, border.y] {
+                tiles.push((BorderSpriteIndex::Hori, Vec2::new(x, y)));
+            }
+        }
+
+        for row in 0..self.header_rows + 1 + self.field_rows {
+            if row == self.header_rows {
+                // skip the join
+                continue;
+            }
+
+            let y = top - border.y * (row as f32 + 1.0);
+            tiles.push((BorderSpriteIndex::Vert, Vec2::new(0.0, y)));
+            tiles.push((BorderSpriteIndex::Vert, Vec2::new(right, y)));
+        }
+
+        // infill on top, and either side of a field narrower than the header
+        for col in 0..self.inner_cols {
+            let x = border.x * (col as f32 + 1.0);
+            for row in 0..self.header_rows {
+                let y = top - border.y * (row as f32 + 1.0);
+                tiles.push((BorderSpriteIndex::Empty, Vec2::new(x, y)));
+            }
+
+            if col < self.field_cols.0 || col >= self.field_cols.1 {
+                for row in 0..self.field_rows {
+                    let y = join - border.y * (row as f32 + 1.0);
+                    tiles.push((BorderSpriteIndex::Empty, Vec2::new(x, y)));
+                }
+            }
+        }
+
+        tiles
+    }
+
+    /// Empty tiles to draw behind the field's last column and row of border tiles when the field
+    /// only covers part of them, which would otherwise leave a gap between the field and the
+    /// frame.
+    pub fn backing_tiles(&self) -> Vec<Vec2> {
+        let border = self.sprites.border.size();
+        let field = self.field_size();
+        let join = self.size.y - border.y * (self.header_rows as f32 + 1.0);
+        let (left, right) = self.field_cols;
+
+        let mut tiles = vec![];
+        let last_col = field.x < border.x * (right - left) as f32;
+        if last_col {
+            let x = border.x * right as f32;
+            for row in 0..self.field_rows {
+                tiles.push(Vec2::new(x, join - border.y * (row as f32 + 1.0)));
+            }
+        }
+        if field.y < border.y * self.field_rows as f32 {
+            let y = join - border.y * self.field_rows as f32;
+            // the last column already has its corner
+            let right = if last_col { right - 1 } else { right };
+            for col in left..right {
+                tiles.push(Vec2::new(border.x * (col as f32 + 1.0), y));
+            }
+        }
+        tiles
+    }
+
+    fn field_size(&self) -> Vec2 {
+        Vec2::new(self.board_size.0 as f32, self.board_size.1 as f32) * self.sprites.field.size()
+    }
+
+    /// The height of the top border, header and the join below it, which stay on screen as the
+    /// HUD while the board is panned and zoomed.
+    pub fn header_height(&self) -> f32 {
+        self.sprites.border.size().y * (self.header_rows as f32 + 2.0)
+    }
+
+    pub fn in_header(&self, position: Vec2) -> bool {
+        position.y > self.size.y - self.header_height()
+    }
+
+    pub fn score_frames(&self) -> [Vec2; 2] {
+        let border = self.sprites.border.size();
+        let y = self.size.y - (border.y + 1.0);
+        [
+            Vec2::new(border.x + 1.0, y),
+            Vec2::new(
+                self.size.x - (border.x + 1.0 + self.sprites.score_frame.size().x),
+                y,
+            ),
+        ]
+    }
+
+    pub fn mine_count(&self) -> Vec2 {
+        let border = self.sprites.border.size();
+        Vec2::new(border.x + 2.0, self.size.y - (border.y + 2.0))
+    }
+
+    pub fn clock(&self) -> Vec2 {
+        self.score_frames()[1] + Vec2::new(1.0, -1.0)
+    }
+
+    pub fn face(&self) -> Vec2 {
+        Vec2::new(
+            ((self.size.x - self.sprites.face.size().x) / 2.0).floor(),
+            self.size.y - self.sprites.border.size().y,
+        )
+    }
+
+    pub fn cell_position(&self, (row, col): (usize, usize)) -> Vec2 {
+        let cell = self.sprites.field.size();
+        Vec2::new(
+            self.minefield_offset.x + col as f32 * cell.x,
+            self.minefield_offset.y - row as f32 * cell.y,
+        )
+    }
+
+    /// The `(row, col)` of the cell under a world position, if any.
+    pub fn cell_at(&self, pos: Vec2) -> Option<(usize, usize)> {
+        let offset = pos - self.minefield_offset;
+        if offset.x < 0.0 || offset.y > 0.0 {
+            return None;
+        }
+
+        let cell = self.sprites.field.size();
+        let row = (-offset.y / cell.y) as usize;
+        let col = (offset.x / cell.x) as usize;
+        (row < self.board_size.1 && col < self.board_size.0).then_some((row, col))
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        PreUpdate,
+        (
+            update_layout.run_if(resource_exists::<SpriteSheetLayout>),
+            rebuild_board.run_if(resource_exists::<Layout>),
+        )
+            .chain(),
+    )
+    .add_systems(PostUpdate, retexture_sprites);
+}
+
+fn update_layout(
+    mut commands: Commands,
+    minefield: Res<Minefield>,
+    sprites: Res<SpriteSheetLayout>,
+    layout: Option<Res<Layout>>,
+) {
+    let board_size = (minefield.cols(), minefield.rows());
+    if layout.is_none_or(|layout| layout.board_size != board_size || layout.sprites != *sprites) {
+        commands.insert_resource(Layout::new(board_size, &sprites));
+    }
+}
+
+fn rebuild_board(
+    mut commands: Commands,
+    layout: Res<Layout>,
+    minefield: Res<Minefield>,
+    settings: Res<Settings>,
+    board_sprites: Query<Entity, With<BoardSprite>>,
+    mut windows: Query<&mut Window>,
+    mut sized_for: Local<Option<(Vec2, f32)>>,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+
+    for entity in board_sprites.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // big boards get a window that fits on screen, and are panned and zoomed instead. Only when
+    // the board's size or the scale changed, so a window resized by hand keeps its size.
+    if sized_for.replace((layout.size, settings.scale)) != Some((layout.size, settings.scale)) {
+        let min_size = layout.size.min(MAX_WINDOW_SIZE / settings.scale);
+        let window_size = (layout.size * settings.scale).min(MAX_WINDOW_SIZE);
+        for mut window in windows.iter_mut() {
+            window.resize_constraints.min_width = min_size.x;
+            window.resize_constraints.min_height = min_size.y;
+            window.resolution.set(window_size.x, window_size.y);
+            if !window.visible {
+                // hidden until the first board to size it for
+                window.position = WindowPosition::Centered(MonitorSelection::Primary);
+                window.visible = true;
+            }
+        }
+    }
+
+    for (index, position) in layout.border_tiles() {
+        commands.queue(SpawnBorderSprite {
+            index,
+            position,
+            hud: layout.in_header(position),
+            behind_field: false,
+        });
+    }
+    for position in layout.backing_tiles() {
+        commands.queue(SpawnBorderSprite {
+            index: BorderSpriteIndex::Empty,
+            position,
+            hud: false,
+            behind_field: true,
+        });
+    }
+
+    for position in layout.score_frames() {
+        commands.queue(SpawnScoreFrame {
+            position,
+            rect: layout.sprites.score_frame.rect(),
+        });
+    }
+
+    commands.queue(SpawnMineCount {
+        position: layout.mine_count(),
+        digit_width: layout.sprites.digits.size().x,
+        value: minefield.mines_left(),
+    });
+
+    commands.queue(SpawnClockCount {
+        position: layout.clock(),
+        digit_width: layout.sprites.digits.size().x,
+        value: 0,
+    });
+
+    commands.queue(SpawnFaceSprite {
+        index: FaceSpriteIndex::Idle,
+        position: layout.face(),
+    });
+
+    for (origin, size) in chunks(layout.board_size) {
+        commands.queue(SpawnMinefieldChunk {
+            origin,
+            size,
+            cell_size: layout.sprites.field.size(),
+            position: layout.cell_position(origin),
+        });
+    }
+}
+
+/// Points every sprite drawn from the old spritesheet image at the new one, when a theme or
+/// palette swaps the image without moving any sprites, so the board needn't be rebuilt.
+fn retexture_sprites(
+    image: Res<SpriteSheetImage>,
+    mut sprites: Query<&mut Sprite>,
+    mut previous: Local<Option<Handle<Image>>>,
+) {
+    if !image.is_changed() {
+        return;
+    }
+    let Some(old) = previous.replace(image.0.clone()) else {
+        return;
+    };
+
+    for mut sprite in sprites.iter_mut() {
+        if sprite.image == old {
+            sprite.image = image.0.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(size: (u32, u32)) -> SpriteGrid {
+        SpriteGrid {
+            size,
+            columns: 16,
+            rows: 1,
+            offset: (0, 0),
+        }
+    }
+
+    /// The bundled themes' sprite sizes, with `cell` pixel square cells.
+    fn sprites(cell: u32) -> SpriteSheetLayout {
+        SpriteSheetLayout {
+            field: grid((cell, cell)),
+            border: grid((8, 8)),
+            face: grid((24, 24)),
+            digits: grid((11, 20)),
+            score_frame: SpriteRect {
+                size: (35, 22),
+                offset: (0, 0),
+            },
+        }
+    }
+
+    fn sorted(tiles: Vec<(BorderSpriteIndex, Vec2)>) -> Vec<(usize, [f32; 2])> {
+        let mut tiles: Vec<_> = tiles
+            .into_iter()
+            .map(|(index, position)| (usize::from(index), position.to_array()))
+            .collect();
+        tiles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        tiles
+    }
+
+    /// Checks that every border-sized square of the window shows a frame or infill tile, or is
+    /// covered by the field and the tiles behind it.
+    fn assert_no_gaps(board_size: (usize, usize), cell: u32) {
+        let layout = Layout::new(board_size, &sprites(cell));
+        let border = layout.sprites.border.size();
+        let tiles = layout.border_tiles();
+        let backing = layout.backing_tiles();
+        let field = layout.field_size();
+        let field_top_left = layout.minefield_offset;
+
+        let cols = (layout.size.x / border.x) as usize;
+        let rows = (layout.size.y / border.y) as usize;
+        assert_eq!(Vec2::new(cols as f32, rows as f32) * border, layout.size);
+        for col in 0..cols {
+            for row in 0..rows {
+                let slot = Vec2::new(border.x * col as f32, layout.size.y - border.y * row as f32);
+                let drawn = tiles.iter().filter(|&&(_, tile)| tile == slot).count();
+                let in_field = slot.x >= field_top_left.x
+                    && slot.x < field_top_left.x + field.x
+                    && slot.y <= field_top_left.y
+                    && slot.y > field_top_left.y - field.y;
+                if !in_field {
+                    assert_eq!(drawn, 1, "{board_size:?} at {slot}");
+                    continue;
+                }
+
+                assert_eq!(drawn, 0, "{board_size:?} draws over the field at {slot}");
+                let covered = slot.x + border.x <= field_top_left.x + field.x
+                    && slot.y - border.y >= field_top_left.y - field.y;
+                assert!(
+                    covered || backing.contains(&slot),
+                    "{board_size:?} leaves a gap at {slot}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn beginner_frame_is_where_it_always_was() {
+        let layout = Layout::new((8, 8), &sprites(16));
+        let (width, height) = (144.0, 176.0);
+        assert_eq!(layout.size, Vec2::new(width, height));
+        assert_eq!(layout.minefield_offset, Vec2::new(8.0, height - 40.0));
+        assert!(layout.backing_tiles().is_empty());
+
+        let mut expected = vec![
+            (BorderSpriteIndex::TopLeftCorner, Vec2::new(0.0, height)),
+            (
+                BorderSpriteIndex::TopRightCorner,
+                Vec2::new(width - 8.0, height),
+            ),
+            (BorderSpriteIndex::BottomLeftCorner, Vec2::new(0.0, 8.0)),
+            (
+                BorderSpriteIndex::BottomRightCorner,
+                Vec2::new(width - 8.0, 8.0),
+            ),
+            (
+                BorderSpriteIndex::JoinVerticalLeft,
+                Vec2::new(0.0, height - 32.0),
+            ),
+            (
+                BorderSpriteIndex::JoinVerticalRight,
+                Vec2::new(width - 8.0, height - 32.0),
+            ),
+        ];
+        for i in 0..16 {
+            let x = 8.0 * (i as f32 + 1.0);
+            for y in [height, height - 32.0, 8.0] {
+                expected.push((BorderSpriteIndex::Hori, Vec2::new(x, y)));
+            }
+        }
+        for i in (0..20).filter(|&i| i != 3) {
+            let y = height - 8.0 * (i as f32 + 1.0);
+            expected.push((BorderSpriteIndex::Vert, Vec2::new(0.0, y)));
+            expected.push((BorderSpriteIndex::Vert, Vec2::new(width - 8.0, y)));
+        }
+        for row in 0..3 {
+            for col in 0..16 {
+                expected.push((
+                    BorderSpriteIndex::Empty,
+                    Vec2::new(8.0 + col as f32 * 8.0, height - (row + 1) as f32 * 8.0),
+                ));
+            }
+        }
+
+        assert_eq!(sorted(layout.border_tiles()), sorted(expected));
+    }
+
+    #[test]
+    fn frames_have_no_gaps() {
+        for (board_size, cell) in [
+            ((8, 8), 16),
+            ((30, 16), 16),
+            ((16, 30), 16),
+            ((1, 1), 16),
+            ((1, 9), 16),
+            ((9, 1), 16),
+            // cells that don't line up with the border tiles
+            ((7, 3), 12),
+            ((1, 1), 12),
+            ((33, 5), 12),
+            ((3, 7), 5),
+        ] {
+            assert_no_gaps(board_size, cell);
+        }
+    }
+
+    #[test]
+    fn narrow_boards_are_centred_under_the_header() {
+        let layout = Layout::new((1, 4), &sprites(16));
+        // the header is 12 border tiles wide, and the field 2
+        assert_eq!(layout.size.x, 8.0 * 14.0);
+        assert_eq!(layout.minefield_offset.x, 8.0 * 6.0);
+        let corner = layout.cell_position((3, 0));
+        assert_eq!(layout.cell_at(corner + Vec2::new(1.0, -1.0)), Some((3, 0)));
+        assert_eq!(layout.cell_at(corner + Vec2::new(17.0, -1.0)), None);
+    }
+}
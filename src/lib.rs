@@ -0,0 +1,35 @@
+#![feature(let_chains)]
+
+pub mod animation;
+pub mod aseprite;
+pub mod autoplay;
+pub mod bot_protocol;
+pub mod clock;
+pub mod commands;
+pub mod config;
+pub mod constants;
+pub mod controls;
+pub mod flag_mode;
+pub mod gamepad;
+pub mod hint;
+pub mod input;
+pub mod keyboard;
+pub mod layout;
+pub mod menu;
+pub mod minefield;
+pub mod palette;
+pub mod pointer;
+pub mod puzzle;
+pub mod puzzle_mode;
+pub mod settings;
+pub mod simple_fps;
+pub mod simulation;
+pub mod solver;
+pub mod sound;
+pub mod spritesheets;
+pub mod statistics;
+pub mod strategy;
+pub mod theme;
+pub mod tilemap;
+pub mod touch;
+pub mod viewport;
@@ -0,0 +1,421 @@
+//! The title screen, the pause menu and the statistics screen. They cover the whole board, so it
+//! can't be studied with the clock stopped. [`Action::Quit`] (Escape) or the window losing focus
+//! pauses the game; Escape again resumes it. Opening the settings or the controls menu during a
+//! game pauses it as well, so they open over the pause menu.
+//!
+//! Also decides when the game gets no input at all: whenever one of these screens, the settings
+//! or the controls menu is showing.
+
+use crate::autoplay::Autoplay;
+use crate::controls::ControlsMenu;
+use crate::input::*;
+use crate::minefield::*;
+use crate::puzzle_mode::{self, PuzzleMode};
+use crate::settings::{Settings, SettingsMenu};
+use crate::statistics::Statistics;
+use bevy::prelude::*;
+use bevy::ui::{FocusPolicy, UiSystem};
+use bevy::window::WindowFocused;
+
+const BACKDROP_COLOR: Color = Color::srgb(0.05, 0.05, 0.08);
+const ITEM_COLOR: Color = Color::NONE;
+const SELECTED_COLOR: Color = Color::srgb(0.25, 0.25, 0.35);
+const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const TITLE_FONT_SIZE: f32 = 32.0;
+const FONT_SIZE: f32 = 20.0;
+const TABLE_FONT_SIZE: f32 = 16.0;
+
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Screen {
+    #[default]
+    Title,
+    Game,
+    Paused,
+    Statistics,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuItem {
+    NewGame,
+    Continue,
+    Resume,
+    Statistics,
+    Settings,
+    Controls,
+    MainMenu,
+    Back,
+    Quit,
+}
+
+impl MenuItem {
+    fn name(self) -> &'static str {
+        match self {
+            MenuItem::NewGame => "New game",
+            MenuItem::Continue => "Continue",
+            MenuItem::Resume => "Resume",
+            MenuItem::Statistics => "Statistics",
+            MenuItem::Settings => "Settings",
+            MenuItem::Controls => "Controls",
+            MenuItem::MainMenu => "Main menu",
+            MenuItem::Back => "Back",
+            MenuItem::Quit => "Quit",
+        }
+    }
+}
+
+/// What a screen offers, top to bottom. Continue only shows with a game to go back to.
+fn items(screen: Screen, minefield: &Minefield) -> Vec<MenuItem> {
+    use MenuItem::*;
+
+    match screen {
+        Screen::Title => {
+            let in_progress = minefield.started() && minefield.state == GameState::Playing;
+            let mut items = vec![NewGame, Statistics, Settings, Controls, Quit];
+            if in_progress {
+                items.insert(1, Continue);
+            }
+            items
+        }
+        Screen::Paused => vec![Resume, NewGame, Settings, Controls, MainMenu, Quit],
+        Screen::Statistics => vec![Back],
+        Screen::Game => vec![],
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+struct Menu {
+    selected: usize,
+}
+
+/// An item picked from the menu, to act on.
+#[derive(Event, Clone, Copy, Debug)]
+struct Chosen(MenuItem);
+
+#[derive(Component)]
+struct MenuRoot;
+
+/// The item at this index of the screen's [`items`].
+#[derive(Component)]
+struct ItemButton {
+    index: usize,
+    item: MenuItem,
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_state::<Screen>()
+        .init_resource::<Menu>()
+        .add_event::<Chosen>()
+        .add_systems(Startup, skip_title)
+        .add_systems(PreUpdate, capture_input.before(update_actions))
+        .add_systems(Update, pause_game)
+        .add_systems(
+            PostUpdate,
+            (
+                // after the other menus' systems, so neither sees the press that switches
+                // between them
+                (navigate_menu, click_items).run_if(nothing_on_top),
+                choose_item,
+                draw_menu,
+            )
+                .chain()
+                .before(UiSystem::Layout),
+        );
+}
+
+/// Bots and puzzles from the command line go straight to the game.
+fn skip_title(
+    autoplay: Res<Autoplay>,
+    puzzle_mode: Option<Res<PuzzleMode>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if autoplay.enabled || puzzle_mode.is_some() {
+        next_screen.set(Screen::Game);
+    }
+}
+
+fn capture_input(
+    screen: Res<State<Screen>>,
+    settings_menu: Res<SettingsMenu>,
+    controls_menu: Res<ControlsMenu>,
+    mut captured: ResMut<InputCaptured>,
+) {
+    let in_menu = *screen.get() != Screen::Game || settings_menu.open || controls_menu.open;
+    if captured.0 != in_menu {
+        captured.0 = in_menu;
+    }
+}
+
+fn pause_game(
+    actions: Res<ButtonInput<Action>>,
+    mut focus_events: EventReader<WindowFocused>,
+    settings_menu: Res<SettingsMenu>,
+    controls_menu: Res<ControlsMenu>,
+    screen: Res<State<Screen>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    let unfocused = focus_events.read().any(|event| !event.focused);
+    let overlay = settings_menu.open || controls_menu.open;
+    if *screen.get() == Screen::Game && (actions.just_pressed(Action::Quit) || unfocused || overlay)
+    {
+        next_screen.set(Screen::Paused);
+    }
+}
+
+/// Whether neither the settings nor the controls menu is open, or was closed this frame.
+fn nothing_on_top(settings_menu: Res<SettingsMenu>, controls_menu: Res<ControlsMenu>) -> bool {
+    !(settings_menu.open
+        || settings_menu.is_changed()
+        || controls_menu.open
+        || controls_menu.is_changed())
+}
+
+/// Runs on raw input, since the menu has the actions to itself while it's open. Gamepads work
+/// it too, with the d-pad, South to choose and East to back out.
+fn navigate_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    screen: Res<State<Screen>>,
+    minefield: Res<Minefield>,
+    mut menu: ResMut<Menu>,
+    mut chosen: EventWriter<Chosen>,
+) {
+    let items = items(*screen.get(), &minefield);
+    let count = items.len();
+    if count == 0 {
+        return;
+    }
+
+    let pad = |button| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad.digital.just_pressed(button))
+    };
+    if keys.just_pressed(KeyCode::ArrowUp) || pad(GamepadButton::DPadUp) {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) || pad(GamepadButton::DPadDown) {
+        menu.selected = (menu.selected + 1) % count;
+    }
+    if keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space])
+        || pad(GamepadButton::South)
+    {
+        chosen.send(Chosen(items[menu.selected.min(count - 1)]));
+    }
+    if keys.just_pressed(KeyCode::Escape) || pad(GamepadButton::East) {
+        match *screen.get() {
+            Screen::Paused => {
+                chosen.send(Chosen(MenuItem::Resume));
+            }
+            Screen::Statistics => {
+                chosen.send(Chosen(MenuItem::Back));
+            }
+            Screen::Title | Screen::Game => {}
+        }
+    }
+}
+
+fn click_items(
+    buttons: Query<(&Interaction, &ItemButton), Changed<Interaction>>,
+    mut menu: ResMut<Menu>,
+    mut chosen: EventWriter<Chosen>,
+) {
+    for (interaction, button) in buttons.iter() {
+        match interaction {
+            Interaction::Pressed => {
+                chosen.send(Chosen(button.item));
+            }
+            Interaction::Hovered if menu.selected != button.index => {
+                menu.selected = button.index;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn choose_item(
+    mut chosen: EventReader<Chosen>,
+    mut minefield: ResMut<Minefield>,
+    mut puzzle_mode: Option<ResMut<PuzzleMode>>,
+    settings: Res<Settings>,
+    mut settings_menu: ResMut<SettingsMenu>,
+    mut controls_menu: ResMut<ControlsMenu>,
+    mut menu: ResMut<Menu>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for &Chosen(item) in chosen.read() {
+        let next = match item {
+            MenuItem::NewGame => {
+                puzzle_mode::new_game(&mut minefield, puzzle_mode.as_deref_mut(), &settings);
+                Screen::Game
+            }
+            MenuItem::Continue | MenuItem::Resume => Screen::Game,
+            MenuItem::Statistics => Screen::Statistics,
+            MenuItem::Settings => {
+                settings_menu.open = true;
+                continue;
+            }
+            MenuItem::Controls => {
+                controls_menu.open();
+                continue;
+            }
+            MenuItem::MainMenu | MenuItem::Back => Screen::Title,
+            MenuItem::Quit => {
+                exit.send(AppExit::Success);
+                continue;
+            }
+        };
+        next_screen.set(next);
+        menu.selected = 0;
+    }
+}
+
+fn draw_menu(
+    mut commands: Commands,
+    screen: Res<State<Screen>>,
+    menu: Res<Menu>,
+    minefield: Res<Minefield>,
+    statistics: Res<Statistics>,
+    roots: Query<Entity, With<MenuRoot>>,
+) {
+    if !screen.is_changed() && !menu.is_changed() && !statistics.is_changed() {
+        return;
+    }
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+    let screen = *screen.get();
+    if screen == Screen::Game {
+        return;
+    }
+
+    let text = |text: String, font_size: f32| {
+        (
+            Text::new(text),
+            TextFont {
+                font_size,
+                ..default()
+            },
+            TextColor(TEXT_COLOR),
+        )
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            // opaque, and over the buttons in the corner too
+            BackgroundColor(BACKDROP_COLOR),
+            FocusPolicy::Block,
+            GlobalZIndex(1),
+            MenuRoot,
+        ))
+        .with_children(|parent| {
+            let title = match screen {
+                Screen::Title => "mines-rs",
+                Screen::Paused => "Paused",
+                Screen::Statistics => "Statistics",
+                Screen::Game => "",
+            };
+            parent
+                .spawn(text(title.into(), TITLE_FONT_SIZE))
+                .insert(Node {
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                });
+
+            if screen == Screen::Statistics {
+                draw_statistics(parent, &statistics);
+            }
+
+            for (index, item) in items(screen, &minefield).into_iter().enumerate() {
+                let selected = index == menu.selected;
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(240.0),
+                            justify_content: JustifyContent::Center,
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(if selected { SELECTED_COLOR } else { ITEM_COLOR }),
+                        ItemButton { index, item },
+                    ))
+                    .with_child(text(item.name().into(), FONT_SIZE));
+            }
+        });
+}
+
+fn draw_statistics(parent: &mut ChildBuilder, statistics: &Statistics) {
+    let row = |parent: &mut ChildBuilder, cells: [String; 5]| {
+        parent
+            .spawn(Node {
+                margin: UiRect::bottom(Val::Px(2.0)),
+                ..default()
+            })
+            .with_children(|row| {
+                for (column, cell) in cells.into_iter().enumerate() {
+                    row.spawn((
+                        Text::new(cell),
+                        TextFont {
+                            font_size: TABLE_FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                        Node {
+                            width: Val::Px(if column == 0 { 240.0 } else { 80.0 }),
+                            ..default()
+                        },
+                    ));
+                }
+            });
+    };
+
+    if statistics.0.is_empty() {
+        row(
+            parent,
+            [
+                "No games finished yet".into(),
+                "".into(),
+                "".into(),
+                "".into(),
+                "".into(),
+            ],
+        );
+    } else {
+        row(
+            parent,
+            ["Board", "Played", "Won", "Win %", "Best"].map(String::from),
+        );
+    }
+    for (board, record) in &statistics.0 {
+        let win_rate = 100.0 * record.won as f32 / record.played.max(1) as f32;
+        row(
+            parent,
+            [
+                board.clone(),
+                record.played.to_string(),
+                record.won.to_string(),
+                format!("{win_rate:.0}%"),
+                record
+                    .best_time
+                    .map_or_else(|| "-".into(), |time| format!("{time:.1}s")),
+            ],
+        );
+    }
+
+    // space before Back
+    parent.spawn(Node {
+        height: Val::Px(16.0),
+        ..default()
+    });
+}
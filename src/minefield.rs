@@ -2,81 +2,662 @@ use crate::spritesheets::MinefieldSpriteIndex;
 use bevy::prelude::*;
 use bevy::utils::HashSet;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 pub const MINEFIELD_SIZE: (usize, usize) = (8, 8);
 pub const MINE_COUNT: usize = 10;
+/// How many actions [`Minefield::undo`] can take back.
+const UNDO_LIMIT: usize = 100;
+
+const NEIGHBOUR_OFFSETS: [[i32; 2]; 8] = [
+    [0, 1],
+    [0, -1],
+    [1, 0],
+    [-1, 0],
+    [1, 1],
+    [1, -1],
+    [-1, 1],
+    [-1, -1],
+];
 
 #[derive(Resource)]
 pub struct Minefield {
     pub cells: Vec<Vec<u32>>,
+    /// Set with [`Minefield::set_flag`], which keeps count of them.
     pub flags: Vec<Vec<bool>>,
     pub hidden: Vec<Vec<bool>>,
+    /// Question marks, which are only a note to the player and don't stop a reveal.
+    pub marks: Vec<Vec<bool>>,
+    pub mine_count: usize,
+    pub state: GameState,
+    pub rules: Rules,
+    /// Where [`FirstClick`] moves the mines to comes from this, if set, so a seeded game plays
+    /// out the same every time.
+    seed: Option<u64>,
+    /// Whether anything has been revealed yet, for [`FirstClick`].
+    started: bool,
+    /// How many of `flags` are set, so the counter doesn't scan the whole field.
+    flag_count: usize,
+    /// What each of the last few actions changed, newest last.
+    history: VecDeque<Move>,
+    /// How many actions [`Minefield::undo`] has taken back this game.
+    undos: usize,
+    /// The cells the action in progress has changed so far, as they were before it.
+    touched: Vec<TouchedCell>,
+    changes: Vec<MinefieldChange>,
+}
+
+/// What one action changed, for [`Minefield::undo`] to put back.
+struct Move {
+    cells: Vec<TouchedCell>,
+    started: bool,
+}
+
+/// A cell as it was before an action revealed or flagged it.
+#[derive(Clone, Copy)]
+struct TouchedCell {
+    position: (usize, usize),
+    hidden: bool,
+    flagged: bool,
+    marked: bool,
 }
 
-#[derive(Component)]
-pub struct MinefieldData {
-    pub position: (usize, usize),
+/// What the first reveal of a game is guaranteed to find.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FirstClick {
+    /// Whatever is there, mines included.
+    #[default]
+    Anything,
+    /// Never a mine.
+    Safe,
+    /// A zero, so it opens a region, where the board has room for one.
+    Opening,
+}
+
+/// Optional rules, kept across [`Minefield::reset`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub first_click: FirstClick,
+    /// Whether flagging a flagged cell leaves a question mark before clearing it.
+    pub question_marks: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameState {
+    Playing,
+    Won,
+    Lost,
+}
+
+/// Everything a player (human or bot) can do to the field, as `(row, col)` positions.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinefieldAction {
+    Reveal(usize, usize),
+    Flag(usize, usize),
+    Chord(usize, usize),
+}
+
+/// What changed on the field since the last frame, so rendering only touches those parts.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinefieldChange {
+    /// The whole field is new, or was changed without going through [`Minefield::apply`].
+    Reset,
+    /// A cell was revealed, or shows something else now that the game is over.
+    Revealed(usize, usize),
+    /// A flag or question mark was placed or removed.
+    Flagged(usize, usize),
+    /// How many mines are left to flag, for the counter.
+    MinesLeft(usize),
 }
 
 impl FromWorld for Minefield {
     fn from_world(_world: &mut World) -> Self {
-        let mut rng = thread_rng();
+        Self::new(MINEFIELD_SIZE, MINE_COUNT, &mut thread_rng())
+    }
+}
 
-        let mut cells = vec![
-            vec![MinefieldSpriteIndex::Num as usize as u32; MINEFIELD_SIZE.0];
-            MINEFIELD_SIZE.1
-        ];
+impl Minefield {
+    /// Generates a `size.0` wide, `size.1` tall field. `mine_count` is capped so that at least
+    /// one cell is safe.
+    pub fn new(size: (usize, usize), mine_count: usize, rng: &mut impl Rng) -> Self {
+        let mine_count = mine_count.min((size.0 * size.1).saturating_sub(1));
 
-        let mut mine_locs: HashSet<UVec2> = HashSet::new();
-        while mine_locs.len() < MINE_COUNT {
-            mine_locs.insert(UVec2::new(
-                rng.gen_range(0..MINEFIELD_SIZE.0) as u32,
-                rng.gen_range(0..MINEFIELD_SIZE.1) as u32,
-            ));
-        }
-        for loc in mine_locs {
-            cells[loc.y as usize][loc.x as usize] = MinefieldSpriteIndex::Mine as u32;
-        }
+        Self::from_mines(&place_mines(size, mine_count, &HashSet::new(), rng))
+    }
+
+    /// Builds a fully hidden field from a row-major mine layout.
+    pub fn from_mines(mines: &[Vec<bool>]) -> Self {
+        let size = (mines.first().map_or(0, |row| row.len()), mines.len());
 
-        for y in 0..MINEFIELD_SIZE.1 {
-            for x in 0..MINEFIELD_SIZE.0 {
-                if cells[y][x] == MinefieldSpriteIndex::Mine as u32 {
+        let mut cells = vec![vec![MinefieldSpriteIndex::Num as usize as u32; size.0]; size.1];
+        let mut mine_count = 0;
+
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                if mines[y][x] {
+                    cells[y][x] = MinefieldSpriteIndex::Mine as u32;
+                    mine_count += 1;
                     continue;
                 }
 
-                cells[y][x] = vec![
-                    [0, 1],
-                    [0, -1],
-                    [1, 0],
-                    [-1, 0],
-                    [1, 1],
-                    [1, -1],
-                    [-1, 1],
-                    [-1, -1],
-                ]
-                .into_iter()
-                .map(|offset| (x as i32 + offset[1], y as i32 + offset[0]))
-                .filter(|coord| {
-                    coord.0 >= 0
-                        && coord.0 < MINEFIELD_SIZE.0 as i32
-                        && coord.1 >= 0
-                        && coord.1 < MINEFIELD_SIZE.1 as i32
-                })
-                .map(|coord| {
-                    cells[coord.1 as usize][coord.0 as usize] == MinefieldSpriteIndex::Mine as u32
-                })
-                .filter(|is_mine| *is_mine)
-                .count() as u32;
+                cells[y][x] = neighbours((y, x), (size.1, size.0))
+                    .filter(|&(row, col)| mines[row][col])
+                    .count() as u32;
             }
         }
 
-        let flags = vec![vec![false; MINEFIELD_SIZE.0]; MINEFIELD_SIZE.1];
-        let hidden = vec![vec![false; MINEFIELD_SIZE.0]; MINEFIELD_SIZE.1];
+        let flags = vec![vec![false; size.0]; size.1];
+        let hidden = vec![vec![true; size.0]; size.1];
+        let marks = vec![vec![false; size.0]; size.1];
 
         Self {
             cells,
             flags,
             hidden,
+            marks,
+            mine_count,
+            state: GameState::Playing,
+            rules: Rules::default(),
+            seed: None,
+            started: false,
+            flag_count: 0,
+            history: VecDeque::new(),
+            undos: 0,
+            touched: vec![],
+            changes: vec![MinefieldChange::Reset],
+        }
+    }
+
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(
+            (self.cols(), self.rows()),
+            self.mine_count,
+            &mut thread_rng(),
+        )
+        .with_rules(self.rules);
+    }
+
+    /// Whether anything has been revealed yet this game.
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    /// How many actions have been taken back this game.
+    pub fn undos(&self) -> usize {
+        self.undos
+    }
+
+    pub fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cells.first().map_or(0, |row| row.len())
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let cols = self.cols();
+        (0..self.rows()).flat_map(move |row| (0..cols).map(move |col| (row, col)))
+    }
+
+    pub fn neighbours(&self, position: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        neighbours(position, (self.rows(), self.cols()))
+    }
+
+    pub fn is_mine(&self, position: (usize, usize)) -> bool {
+        self.cells[position.0][position.1] == MinefieldSpriteIndex::Mine as u32
+    }
+
+    /// The minimum number of clicks needed to clear the field: one per opening (connected region
+    /// of zeroes, plus its border) and one per numbered cell that doesn't border an opening.
+    pub fn bbbv(&self) -> usize {
+        let mut seen = vec![vec![false; self.cols()]; self.rows()];
+        let mut clicks = 0;
+
+        for row in 0..self.rows() {
+            for col in 0..self.cols() {
+                if seen[row][col] || self.cells[row][col] != 0 {
+                    continue;
+                }
+
+                clicks += 1;
+                let mut stack = vec![(row, col)];
+                while let Some((r, c)) = stack.pop() {
+                    if seen[r][c] {
+                        continue;
+                    }
+                    seen[r][c] = true;
+                    if self.cells[r][c] == 0 {
+                        stack.extend(self.neighbours((r, c)));
+                    }
+                }
+            }
+        }
+
+        clicks
+            + self
+                .positions()
+                .filter(|&(row, col)| !seen[row][col] && !self.is_mine((row, col)))
+                .count()
+    }
+
+    /// The mine count less the flags placed, as the counter shows it. This stops at zero on
+    /// purpose rather than going negative like the classic counter, since the counters' digit
+    /// sprites have no minus sign (see [`crate::commands::score_digits`]).
+    pub fn mines_left(&self) -> usize {
+        self.mine_count.saturating_sub(self.flag_count)
+    }
+
+    /// Places or removes a flag without going through [`Minefield::apply`], for setting up a
+    /// board.
+    pub fn set_flag(&mut self, (row, col): (usize, usize), flagged: bool) {
+        if self.flags[row][col] != flagged {
+            self.flags[row][col] = flagged;
+            if flagged {
+                self.flag_count += 1;
+            } else {
+                self.flag_count -= 1;
+            }
+        }
+    }
+
+    /// Everything that changed since the last call.
+    pub fn take_changes(&mut self) -> Vec<MinefieldChange> {
+        std::mem::take(&mut self.changes)
+    }
+
+    pub fn apply(&mut self, action: MinefieldAction) {
+        if self.state != GameState::Playing {
+            return;
+        }
+
+        let started = self.started;
+        match action {
+            MinefieldAction::Reveal(row, col) => self.reveal((row, col)),
+            MinefieldAction::Flag(row, col) => self.toggle_flag((row, col)),
+            MinefieldAction::Chord(row, col) => self.chord((row, col)),
+        }
+
+        if self.state == GameState::Playing && self.all_safe_cells_revealed() {
+            self.win();
         }
+
+        let cells = std::mem::take(&mut self.touched);
+        if self.state == GameState::Playing && !cells.is_empty() {
+            if self.history.len() == UNDO_LIMIT {
+                self.history.pop_front();
+            }
+            self.history.push_back(Move { cells, started });
+        }
+    }
+
+    /// Takes back the last action that changed anything, and returns whether there was one.
+    /// A game that has ended stays that way, so it's only recorded once.
+    pub fn undo(&mut self) -> bool {
+        if self.state != GameState::Playing {
+            return false;
+        }
+        let Some(last) = self.history.pop_back() else {
+            return false;
+        };
+        // mines moved by the first reveal stay where they are, which can't be told apart once
+        // everything is hidden again
+        for cell in last.cells.into_iter().rev() {
+            let (row, col) = cell.position;
+            self.hidden[row][col] = cell.hidden;
+            self.marks[row][col] = cell.marked;
+            self.set_flag(cell.position, cell.flagged);
+        }
+        self.started = last.started;
+        self.undos += 1;
+        self.changes.push(MinefieldChange::Reset);
+        true
+    }
+
+    /// Notes how the cell at `position` is before the action in progress changes it.
+    fn touch(&mut self, (row, col): (usize, usize)) {
+        self.touched.push(TouchedCell {
+            position: (row, col),
+            hidden: self.hidden[row][col],
+            flagged: self.flags[row][col],
+            marked: self.marks[row][col],
+        });
+    }
+
+    fn reveal(&mut self, position: (usize, usize)) {
+        if self.flags[position.0][position.1] || !self.hidden[position.0][position.1] {
+            return;
+        }
+
+        self.settle_mines(position);
+
+        if self.is_mine(position) {
+            self.lose(position);
+            return;
+        }
+
+        let mut stack = vec![position];
+        while let Some((row, col)) = stack.pop() {
+            if !self.hidden[row][col] || self.flags[row][col] {
+                continue;
+            }
+
+            self.touch((row, col));
+            self.hidden[row][col] = false;
+            self.marks[row][col] = false;
+            self.changes.push(MinefieldChange::Revealed(row, col));
+            if self.cells[row][col] == 0 {
+                stack.extend(self.neighbours((row, col)));
+            }
+        }
+    }
+
+    /// Moves the mines for a first reveal at `position`, as the [`FirstClick`] rule says, and
+    /// does nothing once the game has started. Revealing does this itself, so it's only needed
+    /// to see the board the reveal will play on, such as for its [`Minefield::bbbv`].
+    pub fn settle_mines(&mut self, position: (usize, usize)) {
+        if !self.started {
+            self.started = true;
+            self.move_mines_away_from(position);
+        }
+    }
+
+    /// Deals the mines again if the first reveal would break the [`FirstClick`] rule, keeping
+    /// any flags and marks placed so far.
+    fn move_mines_away_from(&mut self, position: (usize, usize)) {
+        let keep_clear: HashSet<(usize, usize)> = match self.rules.first_click {
+            FirstClick::Anything => return,
+            FirstClick::Safe if !self.is_mine(position) => return,
+            FirstClick::Opening if self.cells[position.0][position.1] == 0 => return,
+            FirstClick::Safe => HashSet::from_iter([position]),
+            FirstClick::Opening => self.neighbours(position).chain([position]).collect(),
+        };
+        let room = |keep_clear: &HashSet<_>| self.rows() * self.cols() - keep_clear.len();
+        let keep_clear = if room(&keep_clear) >= self.mine_count {
+            keep_clear
+        } else if self.is_mine(position) && room(&HashSet::new()) > self.mine_count {
+            // too crowded for an opening, but it can still be safe
+            HashSet::from_iter([position])
+        } else {
+            return;
+        };
+
+        let size = (self.cols(), self.rows());
+        let mines = match self.seed {
+            Some(seed) => place_mines(
+                size,
+                self.mine_count,
+                &keep_clear,
+                &mut StdRng::seed_from_u64(seed),
+            ),
+            None => place_mines(size, self.mine_count, &keep_clear, &mut thread_rng()),
+        };
+        let moved = Self::from_mines(&mines);
+        self.cells = moved.cells;
+        self.changes.push(MinefieldChange::Reset);
+    }
+
+    /// Flags a hidden cell, or takes its flag off, going by way of a question mark when
+    /// [`Rules::question_marks`] is on.
+    fn toggle_flag(&mut self, position: (usize, usize)) {
+        if self.hidden[position.0][position.1] {
+            let (row, col) = position;
+            let (flagged, marked) = (self.flags[row][col], self.marks[row][col]);
+            self.touch(position);
+            self.set_flag(position, !flagged && !marked);
+            self.marks[row][col] = flagged && self.rules.question_marks;
+            self.changes
+                .push(MinefieldChange::Flagged(position.0, position.1));
+            self.changes
+                .push(MinefieldChange::MinesLeft(self.mines_left()));
+        }
+    }
+
+    fn chord(&mut self, position: (usize, usize)) {
+        if self.hidden[position.0][position.1] {
+            return;
+        }
+
+        let flagged = self
+            .neighbours(position)
+            .filter(|&(row, col)| self.flags[row][col])
+            .count() as u32;
+        if flagged != self.cells[position.0][position.1] {
+            return;
+        }
+
+        for neighbour in self.neighbours(position).collect::<Vec<_>>() {
+            self.reveal(neighbour);
+            if self.state != GameState::Playing {
+                return;
+            }
+        }
+    }
+
+    fn all_safe_cells_revealed(&self) -> bool {
+        self.positions()
+            .all(|position| self.is_mine(position) || !self.hidden[position.0][position.1])
+    }
+
+    fn win(&mut self) {
+        self.state = GameState::Won;
+        for row in 0..self.rows() {
+            for col in 0..self.cols() {
+                if self.is_mine((row, col)) && !self.flags[row][col] {
+                    self.set_flag((row, col), true);
+                    self.changes.push(MinefieldChange::Flagged(row, col));
+                }
+            }
+        }
+        self.changes
+            .push(MinefieldChange::MinesLeft(self.mines_left()));
+    }
+
+    fn lose(&mut self, hit: (usize, usize)) {
+        self.state = GameState::Lost;
+        for row in 0..self.rows() {
+            for col in 0..self.cols() {
+                if self.is_mine((row, col)) && !self.flags[row][col] {
+                    self.hidden[row][col] = false;
+                } else if !self.is_mine((row, col)) && self.flags[row][col] {
+                    self.set_flag((row, col), false);
+                    self.hidden[row][col] = false;
+                    self.cells[row][col] = MinefieldSpriteIndex::MineMissed as u32;
+                } else {
+                    continue;
+                }
+                self.changes.push(MinefieldChange::Revealed(row, col));
+            }
+        }
+        self.cells[hit.0][hit.1] = MinefieldSpriteIndex::MineHit as u32;
+        self.changes.push(MinefieldChange::Revealed(hit.0, hit.1));
+        self.changes
+            .push(MinefieldChange::MinesLeft(self.mines_left()));
+    }
+}
+
+/// Sends what [`Minefield`] recorded as [`MinefieldChange`] events.
+pub fn send_minefield_changes(
+    mut minefield: ResMut<Minefield>,
+    mut changes: EventWriter<MinefieldChange>,
+) {
+    if minefield.is_changed() {
+        changes.send_batch(minefield.bypass_change_detection().take_changes());
+    }
+}
+
+/// A row-major layout of `mine_count` mines, none of them at the `keep_clear` `(row, col)`
+/// positions. There must be room for them all.
+fn place_mines(
+    size: (usize, usize),
+    mine_count: usize,
+    keep_clear: &HashSet<(usize, usize)>,
+    rng: &mut impl Rng,
+) -> Vec<Vec<bool>> {
+    let mut mines = vec![vec![false; size.0]; size.1];
+
+    let mut mine_locs: HashSet<UVec2> = HashSet::new();
+    while mine_locs.len() < mine_count {
+        let loc = UVec2::new(
+            rng.gen_range(0..size.0) as u32,
+            rng.gen_range(0..size.1) as u32,
+        );
+        if !keep_clear.contains(&(loc.y as usize, loc.x as usize)) {
+            mine_locs.insert(loc);
+        }
+    }
+    for loc in mine_locs {
+        mines[loc.y as usize][loc.x as usize] = true;
+    }
+
+    mines
+}
+
+pub(crate) fn neighbours(
+    position: (usize, usize),
+    size: (usize, usize),
+) -> impl Iterator<Item = (usize, usize)> {
+    NEIGHBOUR_OFFSETS
+        .into_iter()
+        .map(move |offset| (position.0 as i32 + offset[0], position.1 as i32 + offset[1]))
+        .filter(move |coord| {
+            coord.0 >= 0 && coord.0 < size.0 as i32 && coord.1 >= 0 && coord.1 < size.1 as i32
+        })
+        .map(|coord| (coord.0 as usize, coord.1 as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(rows: &[&str], first_click: FirstClick) -> Minefield {
+        let mines: Vec<Vec<bool>> = rows
+            .iter()
+            .map(|row| row.chars().map(|c| c == '*').collect())
+            .collect();
+        Minefield::from_mines(&mines).with_rules(Rules {
+            first_click,
+            question_marks: false,
+        })
+    }
+
+    fn mines(minefield: &Minefield) -> usize {
+        minefield
+            .positions()
+            .filter(|&position| minefield.is_mine(position))
+            .count()
+    }
+
+    #[test]
+    fn bbbv_counts_openings_and_lone_numbers() {
+        // two openings, in the corners without mines, that take in every number between them
+        assert_eq!(
+            field(&["*..", "...", "..*"], FirstClick::Anything).bbbv(),
+            2
+        );
+        // no openings, just numbers
+        assert_eq!(field(&["*.*"], FirstClick::Anything).bbbv(), 1);
+        // an opening on the left and a number cut off from it on the right
+        assert_eq!(
+            field(&["..*.", "..*.", "..*."], FirstClick::Anything).bbbv(),
+            4
+        );
+    }
+
+    #[test]
+    fn first_click_anything_can_lose() {
+        let mut minefield = field(&["*.", ".."], FirstClick::Anything);
+        minefield.apply(MinefieldAction::Reveal(0, 0));
+        assert_eq!(minefield.state, GameState::Lost);
+    }
+
+    #[test]
+    fn first_click_safe_moves_the_mine() {
+        let mut minefield = field(&["*.", ".."], FirstClick::Safe);
+        minefield.apply(MinefieldAction::Reveal(0, 0));
+        assert_ne!(minefield.state, GameState::Lost);
+        assert!(!minefield.is_mine((0, 0)));
+        assert_eq!(mines(&minefield), 1);
+    }
+
+    #[test]
+    fn first_click_opening_clears_the_neighbours() {
+        let mut minefield = field(
+            &[".....", ".....", "..*..", ".....", "....."],
+            FirstClick::Opening,
+        );
+        minefield.apply(MinefieldAction::Reveal(2, 2));
+        assert_eq!(minefield.cells[2][2], 0);
+        assert_eq!(mines(&minefield), 1);
+    }
+
+    #[test]
+    fn seeded_fields_move_mines_the_same_way() {
+        let moved = || {
+            let mut minefield = field(&["*...", "....", "...."], FirstClick::Opening).with_seed(7);
+            minefield.settle_mines((0, 0));
+            minefield.cells
+        };
+        assert_eq!(moved(), moved());
+    }
+
+    #[test]
+    fn first_click_opening_settles_for_safe_when_crowded() {
+        let mut minefield = field(&["**", "*."], FirstClick::Opening);
+        minefield.apply(MinefieldAction::Reveal(0, 0));
+        assert!(!minefield.is_mine((0, 0)));
+        assert_eq!(minefield.state, GameState::Won);
+    }
+
+    #[test]
+    fn undo_takes_back_moves_but_not_endings() {
+        let mut minefield = field(&["*..", "...", "..."], FirstClick::Anything);
+        minefield.apply(MinefieldAction::Flag(0, 0));
+        assert_eq!(minefield.mines_left(), 0);
+        assert!(minefield.undo());
+        assert_eq!(minefield.mines_left(), 1);
+        assert!(!minefield.flags[0][0]);
+        assert!(!minefield.undo());
+        // only the one that took something back counts
+        assert_eq!(minefield.undos(), 1);
+
+        minefield.apply(MinefieldAction::Reveal(0, 0));
+        assert_eq!(minefield.state, GameState::Lost);
+        assert!(!minefield.undo());
+    }
+
+    #[test]
+    fn undo_takes_back_a_flood_fill() {
+        // the cell between the two mines is left for a second move
+        let mut rows = vec![".".repeat(300); 300];
+        rows[0] = format!("*.*{}", ".".repeat(297));
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        let mut minefield = field(&rows, FirstClick::Anything);
+        minefield.apply(MinefieldAction::Reveal(299, 299));
+        minefield.apply(MinefieldAction::Flag(0, 0));
+        assert_eq!(minefield.state, GameState::Playing);
+        // only what changed is kept, not copies of the board
+        assert_eq!(minefield.history.len(), 2);
+        assert_eq!(minefield.history[0].cells.len(), 300 * 300 - 3);
+        assert_eq!(minefield.history[1].cells.len(), 1);
+
+        assert!(minefield.undo());
+        assert!(minefield.undo());
+        assert!(minefield
+            .positions()
+            .all(|(row, col)| minefield.hidden[row][col]));
+        assert_eq!(minefield.mines_left(), 2);
+        assert!(!minefield.started());
     }
 }
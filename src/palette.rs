@@ -0,0 +1,258 @@
+//! Number colours for players who can't rely on the classic ones, picked in the settings screen.
+//! The spritesheet only has the classic colours, so the current theme's image is copied and the
+//! number sprites repainted: a glyph is whatever differs from the empty cell's sprite.
+//!
+//! Whatever the palette, the end of the game doesn't lean on colour either: the mine that went
+//! off is striped, and the cross over a wrongly flagged mine is redrawn in black or white,
+//! whichever stands out from the mine under it.
+
+use crate::settings::Settings;
+use crate::spritesheets::*;
+use crate::theme::apply_theme;
+use crate::tilemap::MinefieldMaterial;
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    /// Whatever the theme's spritesheet has.
+    #[default]
+    Classic,
+    /// Colours that stay apart with deuteranopia and protanopia, from the Okabe-Ito palette.
+    ColorblindSafe,
+    /// Bright numbers on black.
+    HighContrast,
+    /// Every number in one colour, with a pip for each mine along the bottom of the cell as well
+    /// as its digit.
+    Monochrome,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Classic,
+        Palette::ColorblindSafe,
+        Palette::HighContrast,
+        Palette::Monochrome,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Classic => "Classic",
+            Palette::ColorblindSafe => "Colorblind safe",
+            Palette::HighContrast => "High contrast",
+            Palette::Monochrome => "Monochrome",
+        }
+    }
+
+    /// Colours for the numbers 1 to 8, readable on a `dark` or light opened cell.
+    fn numbers(self, dark: bool) -> Option<[[u8; 3]; 8]> {
+        match self {
+            Palette::Classic => None,
+            Palette::ColorblindSafe if dark => Some([
+                [86, 180, 233],
+                [0, 158, 115],
+                [230, 159, 0],
+                [204, 121, 167],
+                [213, 94, 0],
+                [240, 228, 66],
+                [255, 255, 255],
+                [160, 160, 160],
+            ]),
+            Palette::ColorblindSafe => Some([
+                [0, 114, 178],
+                [0, 128, 94],
+                [213, 94, 0],
+                [150, 60, 125],
+                [150, 80, 0],
+                [0, 90, 110],
+                [0, 0, 0],
+                [100, 100, 100],
+            ]),
+            Palette::HighContrast => Some([
+                [90, 170, 255],
+                [0, 230, 0],
+                [255, 80, 80],
+                [255, 120, 255],
+                [255, 210, 0],
+                [0, 230, 230],
+                [255, 255, 255],
+                [170, 170, 170],
+            ]),
+            Palette::Monochrome if dark => Some([[255, 255, 255]; 8]),
+            Palette::Monochrome => Some([[0, 0, 0]; 8]),
+        }
+    }
+}
+
+/// The theme's own image, the repainted copy of it, and which of them is in use.
+#[derive(Default)]
+struct Repainted {
+    source: Handle<Image>,
+    image: Option<Handle<Image>>,
+    shown: Handle<Image>,
+    palette: Palette,
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        apply_palette
+            .after(apply_theme)
+            .run_if(resource_exists::<SpriteSheetLayout>),
+    );
+}
+
+/// Repaints the spritesheet when the palette or the theme changes, and points everything at the
+/// result.
+#[allow(clippy::too_many_arguments)]
+fn apply_palette(
+    settings: Res<Settings>,
+    sprites: Res<SpriteSheetLayout>,
+    material: Res<MinefieldMaterial>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut image: ResMut<SpriteSheetImage>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut repainted: Local<Repainted>,
+) {
+    let mut dirty = sprites.is_changed() || settings.palette != repainted.palette;
+    if image.0 != repainted.shown {
+        // a new theme
+        repainted.source = image.0.clone();
+        dirty = true;
+    }
+    let source = repainted.source.clone();
+    dirty |= image_events
+        .read()
+        .any(|event| event.is_loaded_with_dependencies(&source) || event.is_modified(&source));
+    if !dirty {
+        return;
+    }
+
+    // waits for the theme's image to load first
+    let Some(mut copy) = images.get(&source).cloned() else {
+        return;
+    };
+    let shown = if repaint(&mut copy, &sprites.field, settings.palette) {
+        match &repainted.image {
+            Some(handle) => {
+                images.insert(handle, copy);
+                handle.clone()
+            }
+            None => {
+                let handle = images.add(copy);
+                repainted.image = Some(handle.clone());
+                handle
+            }
+        }
+    } else {
+        source
+    };
+    repainted.palette = settings.palette;
+    repainted.shown = shown.clone();
+
+    if let Some(material) = materials.get_mut(&material.0) {
+        material.texture = Some(shown.clone());
+    }
+    if image.0 != shown {
+        image.0 = shown;
+    }
+}
+
+/// Repaints the number sprites in `image`, and marks the mines apart by more than colour. Only
+/// works on 8-bit RGBA images, which is what themes load as.
+fn repaint(image: &mut Image, field: &SpriteGrid, palette: Palette) -> bool {
+    if !matches!(
+        image.texture_descriptor.format,
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm
+    ) {
+        warn!("Can't change the palette of a spritesheet that isn't 8-bit RGBA");
+        return false;
+    }
+
+    let cells = field.atlas_layout().textures;
+    let cell = |index: MinefieldSpriteIndex, offset: usize| cells[usize::from(index) + offset];
+    let pixel = |image: &Image, at: UVec2| -> [u8; 4] {
+        image
+            .pixel_bytes(at.extend(0))
+            .and_then(|bytes| bytes.try_into().ok())
+            .unwrap_or_default()
+    };
+    let lightness = |[r, g, b, _]: [u8; 4]| (r as u32 + g as u32 + b as u32) / 3;
+    let offsets = |rect: URect| {
+        (0..rect.height()).flat_map(move |y| (0..rect.width()).map(move |x| UVec2::new(x, y)))
+    };
+
+    // how light the empty cell is decides between the light and dark colours
+    let empty = cell(MinefieldSpriteIndex::Num, 0);
+    let empty_lightness = offsets(empty)
+        .map(|offset| lightness(pixel(image, empty.min + offset)))
+        .sum::<u32>()
+        / empty.size().element_product().max(1);
+    let dark = empty_lightness < 128 || palette == Palette::HighContrast;
+    if let Some(colours) = palette.numbers(dark) {
+        // high contrast turns opened cells black, but keeps their grid lines in grey
+        let background = |behind: [u8; 4]| {
+            let grid_line = lightness(behind) < empty_lightness;
+            (palette == Palette::HighContrast).then_some(if grid_line { [96; 3] } else { [0; 3] })
+        };
+        // one pip per mine, every other pixel along the bottom
+        let pip = |number: usize, offset: UVec2| {
+            let x = offset.x as usize;
+            palette == Palette::Monochrome
+                && offset.y + 2 == empty.height()
+                && x % 2 == 1
+                && x < number * 2
+        };
+
+        for offset in offsets(empty) {
+            let behind = pixel(image, empty.min + offset);
+            for number in 0..=8 {
+                let at = cell(MinefieldSpriteIndex::Num, number).min + offset;
+                let [.., alpha] = pixel(image, at);
+                let pip = number > 0 && pip(number, offset);
+                let glyph = (number > 0 && pixel(image, at) != behind) || pip;
+                let alpha = if pip { u8::MAX } else { alpha };
+                let colour = if glyph {
+                    Some(colours[number - 1])
+                } else {
+                    background(behind)
+                };
+                if let Some([r, g, b]) = colour
+                    && let Some(bytes) = image.pixel_bytes_mut(at.extend(0))
+                {
+                    bytes.copy_from_slice(&[r, g, b, alpha]);
+                }
+            }
+        }
+    }
+
+    let (mine, hit, missed) = (
+        cell(MinefieldSpriteIndex::Mine, 0),
+        cell(MinefieldSpriteIndex::MineHit, 0),
+        cell(MinefieldSpriteIndex::MineMissed, 0),
+    );
+    for offset in offsets(mine) {
+        let under = pixel(image, mine.min + offset);
+
+        let at = hit.min + offset;
+        let stripe = (offset.x + offset.y) / 2 % 2 == 0;
+        if stripe
+            && pixel(image, at) != under
+            && let Some(bytes) = image.pixel_bytes_mut(at.extend(0))
+        {
+            bytes[..3].copy_from_slice(&[0, 0, 0]);
+        }
+
+        let at = missed.min + offset;
+        if pixel(image, at) != under
+            && let Some(bytes) = image.pixel_bytes_mut(at.extend(0))
+        {
+            let shade = if lightness(under) < 128 { 255 } else { 0 };
+            bytes[..3].copy_from_slice(&[shade; 3]);
+        }
+    }
+
+    true
+}
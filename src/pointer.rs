@@ -0,0 +1,192 @@
+//! What the mouse is over and holding down, so the board can react before a click is released:
+//! the hovered cell gets a highlight, and hidden cells under a held button look pressed in.
+
+use crate::flag_mode::FlagMode;
+use crate::input::{update_actions, Action};
+use crate::keyboard::CellCursor;
+use crate::layout::Layout;
+use crate::minefield::*;
+use crate::tilemap::*;
+use crate::viewport::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::ui::UiSystem;
+use bevy::utils::HashSet;
+
+const HIGHLIGHT_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.25);
+
+/// The `(row, col)` of the cell under the cursor, unless it's off the field or over the HUD.
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct HoveredCell(pub Option<(usize, usize)>);
+
+/// The reveal, flag and chord inputs whose press started on the field. Releasing one that
+/// started anywhere else does nothing.
+#[derive(Resource, Default, Debug)]
+pub struct ArmedActions(pub HashSet<Action>);
+
+#[derive(Component)]
+struct Highlight;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<HoveredCell>()
+        .init_resource::<ArmedActions>()
+        .add_systems(Startup, spawn_highlight)
+        .add_systems(
+            PreUpdate,
+            (
+                track_hovered_cell.run_if(resource_exists::<Layout>),
+                arm_actions,
+            )
+                .chain()
+                .after(UiSystem::Focus)
+                .after(update_actions),
+        )
+        .add_systems(
+            Update,
+            (
+                press_cells.before(update_minefield_chunks),
+                move_highlight.run_if(resource_exists::<Layout>),
+            ),
+        );
+}
+
+fn spawn_highlight(mut commands: Commands) {
+    commands.spawn((
+        Sprite {
+            color: HIGHLIGHT_COLOR,
+            anchor: Anchor::TopLeft,
+            ..default()
+        },
+        Transform::default(),
+        Visibility::Hidden,
+        Highlight,
+    ));
+}
+
+/// Finds the cell under a point in the window, for anything that points at the board.
+#[derive(SystemParam)]
+pub struct CellPicker<'w, 's> {
+    camera: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<BoardCamera>>,
+    window: Query<'w, 's, &'static Window>,
+    ui: Query<'w, 's, &'static Interaction>,
+    camera_control: Res<'w, CameraControl>,
+    layout: Res<'w, Layout>,
+    minefield: Res<'w, Minefield>,
+}
+
+impl CellPicker<'_, '_> {
+    pub fn cursor_position(&self) -> Option<Vec2> {
+        self.window.get_single().ok()?.cursor_position()
+    }
+
+    /// The `(row, col)` of the cell under `position`, in logical window coordinates, unless
+    /// it's off the field or over the HUD or a button.
+    pub fn cell_at(&self, position: Vec2) -> Option<(usize, usize)> {
+        if self
+            .ui
+            .iter()
+            .all(|interaction| *interaction == Interaction::None)
+            && let Ok((camera, camera_transform)) = self.camera.get_single()
+            && let Ok(window) = self.window.get_single()
+            && !self.camera_control.over_hud(window, position)
+            && let Ok(ray) = camera.viewport_to_world(camera_transform, position)
+            && let Some((row, col)) = self
+                .layout
+                .cell_at(Vec2::new(ray.origin.x.floor(), ray.origin.y.ceil()))
+            && row < self.minefield.rows()
+            && col < self.minefield.cols()
+        {
+            Some((row, col))
+        } else {
+            None
+        }
+    }
+}
+
+fn track_hovered_cell(picker: CellPicker, mut hovered: ResMut<HoveredCell>) {
+    let cell = picker
+        .cursor_position()
+        .and_then(|cursor| picker.cell_at(cursor));
+    hovered.set_if_neq(HoveredCell(cell));
+}
+
+fn arm_actions(
+    actions: Res<ButtonInput<Action>>,
+    hovered: Res<HoveredCell>,
+    mut armed: ResMut<ArmedActions>,
+) {
+    for action in [Action::Reveal, Action::Flag, Action::Chord] {
+        if actions.just_pressed(action) && hovered.0.is_some() {
+            armed.0.insert(action);
+        } else if !actions.pressed(action) && !actions.just_released(action) {
+            // kept for the frame it's released in, so the release can act
+            armed.0.remove(&action);
+        }
+    }
+}
+
+/// Presses in the hidden cells a release would act on: the hovered one while reveal is held, or
+/// the ones around it when that would chord.
+#[allow(clippy::too_many_arguments)]
+fn press_cells(
+    actions: Res<ButtonInput<Action>>,
+    armed: Res<ArmedActions>,
+    camera_control: Res<CameraControl>,
+    cursor: Res<CellCursor>,
+    flag_mode: Res<FlagMode>,
+    hovered: Res<HoveredCell>,
+    minefield: Res<Minefield>,
+    mut pressed: ResMut<PressedCells>,
+    mut redraws: EventWriter<RedrawCell>,
+) {
+    let mut cells = HashSet::new();
+    if minefield.state == GameState::Playing
+        && !camera_control.dragged
+        && !cursor.active
+        && let Some((row, col)) = hovered.0
+        && row < minefield.rows()
+        && col < minefield.cols()
+    {
+        let (reveal, flag) = flag_mode.actions();
+        let pressed = |action| actions.pressed(action) && armed.0.contains(&action);
+        let chording = pressed(Action::Chord)
+            || (!minefield.hidden[row][col] && (pressed(reveal) || (flag_mode.0 && pressed(flag))));
+        if chording {
+            cells.extend(minefield.neighbours((row, col)));
+            cells.insert((row, col));
+        } else if pressed(reveal) {
+            cells.insert((row, col));
+        }
+        cells.retain(|&(row, col)| minefield.hidden[row][col] && !minefield.flags[row][col]);
+    }
+
+    if pressed.0 != cells {
+        for &(row, col) in pressed.0.symmetric_difference(&cells) {
+            redraws.send(RedrawCell(row, col));
+        }
+        pressed.0 = cells;
+    }
+}
+
+fn move_highlight(
+    hovered: Res<HoveredCell>,
+    layout: Res<Layout>,
+    minefield: Res<Minefield>,
+    mut highlight: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<Highlight>>,
+) {
+    if !hovered.is_changed() && !layout.is_changed() && !minefield.is_changed() {
+        return;
+    }
+
+    for (mut sprite, mut transform, mut visibility) in highlight.iter_mut() {
+        match hovered.0 {
+            Some(cell) if minefield.state == GameState::Playing => {
+                sprite.custom_size = Some(layout.sprites.field.size());
+                transform.translation = layout.cell_position(cell).extend(0.5);
+                *visibility = Visibility::Visible;
+            }
+            _ => *visibility = Visibility::Hidden,
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! Handcrafted logic puzzles: partially revealed boards where the player has to make one
+//! deduction, or a short chain of them.
+//!
+//! A puzzle file holds puzzles separated by blank lines. Each puzzle starts with
+//! `puzzle <name>` followed by one line per row, using
+//!
+//! - `0`-`8` for a revealed number,
+//! - `.` for a hidden safe cell and `*` for a hidden mine,
+//! - `F` for a flagged mine,
+//! - `?` for a safe cell the player must reveal and `!` for a mine the player must flag.
+//!
+//! Lines starting with `;` are comments.
+
+use crate::minefield::*;
+use crate::solver::*;
+use crate::strategy::*;
+use std::collections::HashSet;
+use std::fmt;
+
+pub const BUNDLED_PUZZLES: &str = include_str!("../assets/puzzles.txt");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PuzzleCell {
+    Revealed(u8),
+    Hidden,
+    Mine,
+    Flagged,
+    RevealTarget,
+    FlagTarget,
+}
+
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub name: String,
+    cells: Vec<Vec<PuzzleCell>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PuzzleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+pub fn parse_puzzles(source: &str) -> Result<Vec<Puzzle>, PuzzleError> {
+    let mut puzzles: Vec<Puzzle> = vec![];
+    let mut current: Option<(usize, Puzzle)> = None;
+
+    let finish = |current: Option<(usize, Puzzle)>, puzzles: &mut Vec<Puzzle>| match current {
+        Some((line, puzzle)) if puzzle.cells.is_empty() => Err(PuzzleError {
+            line,
+            message: format!("puzzle '{}' has no rows", puzzle.name),
+        }),
+        Some((line, puzzle)) if puzzle.targets().next().is_none() => Err(PuzzleError {
+            line,
+            message: format!("puzzle '{}' has no ? or ! cells", puzzle.name),
+        }),
+        Some((_, puzzle)) => {
+            puzzles.push(puzzle);
+            Ok(())
+        }
+        None => Ok(()),
+    };
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+
+        if line.starts_with(';') {
+            continue;
+        }
+        if line.is_empty() {
+            finish(current.take(), &mut puzzles)?;
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix("puzzle") {
+            finish(current.take(), &mut puzzles)?;
+            current = Some((
+                line_number,
+                Puzzle {
+                    name: name.trim().to_string(),
+                    cells: vec![],
+                },
+            ));
+            continue;
+        }
+
+        let Some((_, puzzle)) = &mut current else {
+            return Err(PuzzleError {
+                line: line_number,
+                message: "expected 'puzzle <name>'".into(),
+            });
+        };
+
+        let row = line
+            .chars()
+            .map(|c| match c {
+                '0'..='8' => Ok(PuzzleCell::Revealed(c as u8 - b'0')),
+                '.' => Ok(PuzzleCell::Hidden),
+                '*' => Ok(PuzzleCell::Mine),
+                'F' => Ok(PuzzleCell::Flagged),
+                '?' => Ok(PuzzleCell::RevealTarget),
+                '!' => Ok(PuzzleCell::FlagTarget),
+                _ => Err(PuzzleError {
+                    line: line_number,
+                    message: format!("unexpected cell '{c}'"),
+                }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(first) = puzzle.cells.first()
+            && first.len() != row.len()
+        {
+            return Err(PuzzleError {
+                line: line_number,
+                message: format!("expected {} cells, found {}", first.len(), row.len()),
+            });
+        }
+        puzzle.cells.push(row);
+    }
+    finish(current, &mut puzzles)?;
+
+    Ok(puzzles)
+}
+
+impl Puzzle {
+    /// The board size as `(width, height)`, matching `MINEFIELD_SIZE`.
+    pub fn size(&self) -> (usize, usize) {
+        (
+            self.cells.first().map_or(0, |row| row.len()),
+            self.cells.len(),
+        )
+    }
+
+    fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.cells.len())
+            .flat_map(move |row| (0..self.cells[row].len()).map(move |col| (row, col)))
+    }
+
+    fn get(&self, position: (usize, usize)) -> PuzzleCell {
+        self.cells[position.0][position.1]
+    }
+
+    fn targets(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.positions().filter(|&position| {
+            matches!(
+                self.get(position),
+                PuzzleCell::RevealTarget | PuzzleCell::FlagTarget
+            )
+        })
+    }
+
+    pub fn minefield(&self) -> Minefield {
+        let mines: Vec<Vec<bool>> = self
+            .cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        matches!(
+                            cell,
+                            PuzzleCell::Mine | PuzzleCell::Flagged | PuzzleCell::FlagTarget
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut minefield = Minefield::from_mines(&mines);
+        for (row, col) in self.positions() {
+            match self.get((row, col)) {
+                PuzzleCell::Revealed(_) => minefield.hidden[row][col] = false,
+                PuzzleCell::Flagged => minefield.set_flag((row, col), true),
+                _ => {}
+            }
+        }
+        minefield
+    }
+
+    /// Whether every `?` has been revealed and every `!` flagged.
+    pub fn is_solved(&self, minefield: &Minefield) -> bool {
+        self.targets().all(|(row, col)| match self.get((row, col)) {
+            PuzzleCell::FlagTarget => minefield.flags[row][col],
+            _ => !minefield.hidden[row][col],
+        })
+    }
+
+    /// Whether the player has lost, or revealed or flagged a cell that can't be deduced. Flags
+    /// don't count once the board is won, since winning flags every mine.
+    pub fn is_failed(&self, minefield: &Minefield) -> bool {
+        let won = minefield.state == GameState::Won;
+        minefield.state == GameState::Lost
+            || self.positions().any(|(row, col)| {
+                matches!(self.get((row, col)), PuzzleCell::Hidden | PuzzleCell::Mine)
+                    && ((minefield.flags[row][col] && !won) || !minefield.hidden[row][col])
+            })
+    }
+
+    /// Checks that the written numbers match the mines, and that the logic solver deduces exactly
+    /// the `?` and `!` cells, no more and no less.
+    pub fn check(&self) -> Result<(), String> {
+        let minefield = self.minefield();
+
+        for (row, col) in self.positions() {
+            if let PuzzleCell::Revealed(n) = self.get((row, col))
+                && minefield.cells[row][col] != n as u32
+            {
+                return Err(format!(
+                    "cell ({row}, {col}) is written as {n} but borders {} mines",
+                    minefield.cells[row][col]
+                ));
+            }
+        }
+
+        let max_moves = 4 * minefield.rows() * minefield.cols();
+        let mut harness = Harness::new(minefield);
+        let mut deduced = HashSet::new();
+        for _ in 0..max_moves {
+            let before = harness.view();
+            match harness.step(&mut SolverStrategy::Logic) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => return Err(e.to_string()),
+            }
+            let after = harness.view();
+            // winning flags every mine left, which the solver didn't work out for itself
+            let won = after.state() == GameState::Won;
+            deduced.extend(self.positions().filter(|&position| {
+                before.get(position) != after.get(position)
+                    && !(won && after.get(position) == CellView::Flagged)
+            }));
+        }
+        if harness.state() == GameState::Lost {
+            return Err("the solver hit a mine".into());
+        }
+
+        for position in self.positions() {
+            let deduced = deduced.contains(&position);
+            let target = matches!(
+                self.get(position),
+                PuzzleCell::RevealTarget | PuzzleCell::FlagTarget
+            );
+
+            if deduced && !target {
+                return Err(format!(
+                    "cell {position:?} can also be deduced but isn't marked with ? or !"
+                ));
+            }
+            if target && !deduced {
+                return Err(format!("cell {position:?} can't be deduced"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_line(source: &str) -> usize {
+        parse_puzzles(source).unwrap_err().line
+    }
+
+    #[test]
+    fn bundled_puzzles_check_out() {
+        let puzzles = parse_puzzles(BUNDLED_PUZZLES).unwrap();
+        assert!(!puzzles.is_empty());
+        for puzzle in &puzzles {
+            assert_eq!(puzzle.check(), Ok(()), "puzzle '{}'", puzzle.name);
+        }
+    }
+
+    #[test]
+    fn rejects_rows_of_different_lengths() {
+        assert_eq!(error_line("puzzle ragged\n1?.\n*1\n"), 3);
+    }
+
+    #[test]
+    fn rejects_unknown_cells() {
+        let error = parse_puzzles("puzzle typo\n1?x\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("'x'"));
+    }
+
+    #[test]
+    fn rejects_rows_outside_a_puzzle() {
+        assert_eq!(error_line("; no header\n*1?\n"), 2);
+    }
+
+    #[test]
+    fn rejects_puzzles_without_rows_or_targets() {
+        assert_eq!(error_line("puzzle empty\n\npuzzle next\n*1?\n"), 1);
+        assert_eq!(error_line("puzzle nothing to do\n*1.\n"), 1);
+    }
+
+    #[test]
+    fn finishing_the_board_only_deduces_the_targets() {
+        // the last reveal wins, which flags the mine on the right without it being deduced
+        let puzzle = &parse_puzzles("puzzle last move\nF1?1*\n").unwrap()[0];
+        assert_eq!(puzzle.check(), Ok(()));
+    }
+
+    #[test]
+    fn winning_is_not_failing() {
+        let puzzle = &parse_puzzles("puzzle won\n*1?\n").unwrap()[0];
+        let mut minefield = puzzle.minefield();
+        minefield.apply(MinefieldAction::Reveal(0, 2));
+
+        assert_eq!(minefield.state, GameState::Won);
+        assert!(puzzle.is_solved(&minefield));
+        assert!(!puzzle.is_failed(&minefield));
+    }
+}
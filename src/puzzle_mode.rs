@@ -0,0 +1,154 @@
+use crate::minefield::*;
+use crate::puzzle::*;
+use crate::settings::Settings;
+use bevy::prelude::*;
+
+const NEXT_PUZZLE_DELAY: f32 = 1.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PuzzleStatus {
+    Playing,
+    Solved,
+    Failed,
+}
+
+#[derive(Resource)]
+pub struct PuzzleMode {
+    pub puzzles: Vec<Puzzle>,
+    pub current: usize,
+    pub status: PuzzleStatus,
+    timer: Timer,
+}
+
+impl PuzzleMode {
+    pub fn new(puzzles: Vec<Puzzle>) -> Self {
+        Self {
+            puzzles,
+            current: 0,
+            status: PuzzleStatus::Playing,
+            timer: Timer::from_seconds(NEXT_PUZZLE_DELAY, TimerMode::Once),
+        }
+    }
+
+    /// Reads `--puzzles` for the bundled puzzles or `--puzzles=<file>` for your own.
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Option<Self> {
+        let source = match args.find(|arg| arg.starts_with("--puzzles"))?.as_str() {
+            "--puzzles" => BUNDLED_PUZZLES.to_string(),
+            arg => {
+                let file = arg.strip_prefix("--puzzles=")?;
+                match std::fs::read_to_string(file) {
+                    Ok(source) => source,
+                    Err(e) => {
+                        error!("Failed to read puzzles from '{file}': {e}");
+                        return None;
+                    }
+                }
+            }
+        };
+
+        let puzzles = match parse_puzzles(&source) {
+            Ok(puzzles) => puzzles,
+            Err(e) => {
+                error!("Failed to parse puzzles: {e}");
+                return None;
+            }
+        };
+
+        if puzzles.is_empty() {
+            warn!("No puzzles to play");
+            return None;
+        }
+
+        Some(Self::new(puzzles))
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzles[self.current]
+    }
+}
+
+/// Starts a new game: the current puzzle over again in puzzle mode, or else a new board of the
+/// size in the settings.
+pub fn new_game(
+    minefield: &mut Minefield,
+    puzzle_mode: Option<&mut PuzzleMode>,
+    settings: &Settings,
+) {
+    match puzzle_mode {
+        Some(puzzle_mode) => {
+            puzzle_mode.status = PuzzleStatus::Playing;
+            *minefield = puzzle_mode.puzzle().minefield();
+        }
+        None => *minefield = settings.minefield(),
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    if let Some(puzzle_mode) = PuzzleMode::from_args(std::env::args().skip(1)) {
+        app.insert_resource(puzzle_mode.puzzle().minefield())
+            .insert_resource(puzzle_mode);
+    }
+
+    app.add_systems(
+        Update,
+        (check_puzzle, next_puzzle, update_puzzle_title)
+            .chain()
+            .run_if(resource_exists::<PuzzleMode>),
+    );
+}
+
+fn check_puzzle(minefield: Res<Minefield>, mut puzzle_mode: ResMut<PuzzleMode>) {
+    if puzzle_mode.status != PuzzleStatus::Playing || !minefield.is_changed() {
+        return;
+    }
+
+    let puzzle = puzzle_mode.puzzle();
+    let status = if puzzle.is_failed(&minefield) {
+        PuzzleStatus::Failed
+    } else if puzzle.is_solved(&minefield) {
+        PuzzleStatus::Solved
+    } else {
+        return;
+    };
+
+    puzzle_mode.status = status;
+    puzzle_mode.timer.reset();
+}
+
+fn next_puzzle(
+    time: Res<Time>,
+    mut minefield: ResMut<Minefield>,
+    mut puzzle_mode: ResMut<PuzzleMode>,
+) {
+    if puzzle_mode.status == PuzzleStatus::Playing
+        || !puzzle_mode.timer.tick(time.delta()).just_finished()
+    {
+        return;
+    }
+
+    if puzzle_mode.status == PuzzleStatus::Solved {
+        puzzle_mode.current = (puzzle_mode.current + 1) % puzzle_mode.puzzles.len();
+    }
+    puzzle_mode.status = PuzzleStatus::Playing;
+    *minefield = puzzle_mode.puzzle().minefield();
+}
+
+fn update_puzzle_title(puzzle_mode: Res<PuzzleMode>, mut windows: Query<&mut Window>) {
+    if !puzzle_mode.is_changed() {
+        return;
+    }
+
+    let status = match puzzle_mode.status {
+        PuzzleStatus::Playing => "",
+        PuzzleStatus::Solved => " - solved!",
+        PuzzleStatus::Failed => " - not quite, try again",
+    };
+    for mut window in windows.iter_mut() {
+        window.title = format!(
+            "mines-rs - puzzle {}/{}: {}{status}",
+            puzzle_mode.current + 1,
+            puzzle_mode.puzzles.len(),
+            puzzle_mode.puzzle().name
+        );
+    }
+}
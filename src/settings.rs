@@ -0,0 +1,763 @@
+//! The settings screen, opened with F2 or the menu button in the corner: the board, the rules,
+//! animations, sound, skin, number colours, window scale, mouse buttons and the touchscreen's
+//! long press. Up and down pick a setting and left and right change it (in tens for board sizes
+//! while Shift is held), or click the arrows either side of it. Changes apply straight away and
+//! are saved to `settings.ron` in the config directory (see [`crate::config`]), except that a
+//! new board waits for the next game rather than throwing away one under way.
+//!
+//! `--theme=<name>`, `--no-animations`, `--swap-buttons` and `--long-press=<seconds>` override
+//! what was saved.
+
+use crate::animation::Animations;
+use crate::config;
+use crate::constants::SCALE;
+use crate::input::*;
+use crate::layout::Layout;
+use crate::minefield::*;
+use crate::palette::Palette;
+use crate::puzzle_mode::PuzzleMode;
+use crate::sound::SoundCategory;
+use crate::theme::*;
+use crate::touch::{TouchSettings, DEFAULT_LONG_PRESS};
+use bevy::prelude::*;
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+const SETTINGS_FILE: &str = "settings.ron";
+/// The widest or tallest custom board, big enough for the 1000x1000 boards the chunked tilemap
+/// (see [`crate::tilemap`]) is built to draw.
+const MAX_SIDE: usize = 1000;
+/// The most mines on a custom board, which keeps the mine counter within its three digits.
+const MAX_MINES: usize = 999;
+const MAX_SCALE: f32 = 4.0;
+const VOLUME_STEP: f32 = 0.1;
+/// Seconds, which is also the shortest long press, since one of no time at all would flag every
+/// touch.
+const LONG_PRESS_STEP: f32 = 0.1;
+const MAX_LONG_PRESS: f32 = 2.0;
+
+const BUTTON_SIZE: f32 = 40.0;
+const BUTTON_MARGIN: f32 = 8.0;
+const BUTTON_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.3);
+const BACKDROP_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+const ROW_COLOR: Color = Color::NONE;
+const SELECTED_COLOR: Color = Color::srgb(0.25, 0.25, 0.35);
+const ARROW_COLOR: Color = Color::srgb(0.35, 0.35, 0.45);
+const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const FONT_SIZE: f32 = 16.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Expert,
+    Custom,
+}
+
+impl Difficulty {
+    const ALL: [Difficulty; 4] = [
+        Difficulty::Beginner,
+        Difficulty::Intermediate,
+        Difficulty::Expert,
+        Difficulty::Custom,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Intermediate => "Intermediate",
+            Difficulty::Expert => "Expert",
+            Difficulty::Custom => "Custom",
+        }
+    }
+
+    /// The preset's board, which [`Difficulty::Custom`] doesn't have.
+    pub fn board(self) -> Option<BoardSize> {
+        let (width, height, mines) = match self {
+            Difficulty::Beginner => (MINEFIELD_SIZE.0, MINEFIELD_SIZE.1, MINE_COUNT),
+            Difficulty::Intermediate => (16, 16, 40),
+            Difficulty::Expert => (30, 16, 99),
+            Difficulty::Custom => return None,
+        };
+        Some(BoardSize {
+            width,
+            height,
+            mines,
+        })
+    }
+}
+
+/// A board's size in cells, and how many mines it has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardSize {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+}
+
+impl BoardSize {
+    /// Keeps the board between one cell and [`MAX_SIDE`] a side, with at least one mine and one
+    /// safe cell, and no more than [`MAX_MINES`]. A board one cell wide is at least two tall, so
+    /// there's room for both.
+    fn clamped(self) -> Self {
+        let width = self.width.clamp(1, MAX_SIDE);
+        let height = self.height.clamp(if width == 1 { 2 } else { 1 }, MAX_SIDE);
+        let cells = width * height;
+        Self {
+            width,
+            height,
+            mines: self.mines.clamp(1, (cells - 1).min(MAX_MINES)),
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    /// The board for [`Difficulty::Custom`].
+    pub custom: BoardSize,
+    pub first_click: FirstClick,
+    pub question_marks: bool,
+    pub animations: bool,
+    /// From 0 (muted) to 1.
+    pub volume: f32,
+    pub muted: BTreeSet<SoundCategory>,
+    /// The name of the skin, from [`Themes`].
+    pub theme: String,
+    /// The colours of the numbers (see [`crate::palette`]).
+    pub palette: Palette,
+    /// Window pixels per sprite pixel, when a new board sizes the window.
+    pub scale: f32,
+    /// Swaps the left and right mouse buttons, for left-handed mice.
+    pub swap_buttons: bool,
+    /// Seconds a finger has to stay down on a touchscreen to flag instead of reveal.
+    pub long_press: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::Beginner,
+            custom: BoardSize {
+                width: 30,
+                height: 20,
+                mines: 120,
+            },
+            first_click: FirstClick::default(),
+            question_marks: false,
+            animations: true,
+            volume: 1.0,
+            muted: BTreeSet::from([SoundCategory::Clock]),
+            theme: DEFAULT_THEME.to_string(),
+            palette: Palette::default(),
+            scale: SCALE,
+            swap_buttons: false,
+            long_press: DEFAULT_LONG_PRESS,
+        }
+    }
+}
+
+impl Settings {
+    /// The saved settings, with the command line on top.
+    pub fn load(args: impl Iterator<Item = String>) -> Self {
+        let mut settings: Self = config::load(SETTINGS_FILE).unwrap_or_default();
+        for arg in args {
+            if let Some(theme) = arg.strip_prefix("--theme=") {
+                settings.theme = theme.to_string();
+            } else if arg == "--no-animations" {
+                settings.animations = false;
+            } else if arg == "--swap-buttons" {
+                settings.swap_buttons = true;
+            } else if let Some(seconds) = arg.strip_prefix("--long-press=") {
+                match seconds.parse() {
+                    Ok(seconds) => settings.long_press = seconds,
+                    Err(e) => warn!("Ignoring long press delay '{seconds}': {e}"),
+                }
+            }
+        }
+        if !(LONG_PRESS_STEP..=MAX_LONG_PRESS).contains(&settings.long_press) {
+            warn!(
+                "Long press delay {}s isn't between {LONG_PRESS_STEP}s and {MAX_LONG_PRESS}s, \
+                 using {DEFAULT_LONG_PRESS}s",
+                settings.long_press
+            );
+            settings.long_press = DEFAULT_LONG_PRESS;
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        config::save(SETTINGS_FILE, self);
+    }
+
+    pub fn board(&self) -> BoardSize {
+        self.difficulty
+            .board()
+            .unwrap_or_else(|| self.custom.clamped())
+    }
+
+    pub fn rules(&self) -> Rules {
+        Rules {
+            first_click: self.first_click,
+            question_marks: self.question_marks,
+        }
+    }
+
+    /// A new game on the board and rules these settings are for.
+    pub fn minefield(&self) -> Minefield {
+        let board = self.board();
+        Minefield::new((board.width, board.height), board.mines, &mut thread_rng())
+            .with_rules(self.rules())
+    }
+}
+
+/// What a board is called in the statistics: the preset it matches, or its size.
+pub fn board_name(width: usize, height: usize, mines: usize) -> String {
+    let board = BoardSize {
+        width,
+        height,
+        mines,
+    };
+    Difficulty::ALL
+        .into_iter()
+        .find(|difficulty| difficulty.board() == Some(board))
+        .map_or_else(
+            || format!("{width}x{height}, {mines} mines"),
+            |difficulty| difficulty.name().to_string(),
+        )
+}
+
+/// One line of the settings screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Row {
+    Difficulty,
+    Width,
+    Height,
+    Mines,
+    FirstClick,
+    QuestionMarks,
+    Animations,
+    Volume,
+    Mute(SoundCategory),
+    Skin,
+    Palette,
+    Scale,
+    SwapButtons,
+    LongPress,
+}
+
+impl Row {
+    const ALL: [Row; 17] = [
+        Row::Difficulty,
+        Row::Width,
+        Row::Height,
+        Row::Mines,
+        Row::FirstClick,
+        Row::QuestionMarks,
+        Row::Animations,
+        Row::Volume,
+        Row::Mute(SoundCategory::Cells),
+        Row::Mute(SoundCategory::Flags),
+        Row::Mute(SoundCategory::Endings),
+        Row::Mute(SoundCategory::Clock),
+        Row::Skin,
+        Row::Palette,
+        Row::Scale,
+        Row::SwapButtons,
+        Row::LongPress,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Row::Difficulty => "Difficulty",
+            Row::Width => "Width",
+            Row::Height => "Height",
+            Row::Mines => "Mines",
+            Row::FirstClick => "First click",
+            Row::QuestionMarks => "Question marks",
+            Row::Animations => "Animations",
+            Row::Volume => "Volume",
+            Row::Mute(category) => category.name(),
+            Row::Skin => "Skin",
+            Row::Palette => "Numbers",
+            Row::Scale => "Scale",
+            Row::SwapButtons => "Swap mouse buttons",
+            Row::LongPress => "Long press",
+        }
+    }
+
+    fn value(self, settings: &Settings) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+        match self {
+            Row::Difficulty => settings.difficulty.name().into(),
+            Row::Width => settings.board().width.to_string(),
+            Row::Height => settings.board().height.to_string(),
+            Row::Mines => settings.board().mines.to_string(),
+            Row::FirstClick => match settings.first_click {
+                FirstClick::Anything => "Anything goes",
+                FirstClick::Safe => "Never a mine",
+                FirstClick::Opening => "Always an opening",
+            }
+            .into(),
+            Row::QuestionMarks => on_off(settings.question_marks),
+            Row::Animations => on_off(settings.animations),
+            Row::Volume => format!("{:.0}%", settings.volume * 100.0),
+            Row::Mute(category) => on_off(!settings.muted.contains(&category)),
+            Row::Skin => settings.theme.clone(),
+            Row::Palette => settings.palette.name().into(),
+            Row::Scale => format!("{}x", settings.scale),
+            Row::SwapButtons => on_off(settings.swap_buttons),
+            Row::LongPress => format!("{:.1}s", settings.long_press),
+        }
+    }
+
+    /// Moves the setting `by` steps, which only board sizes take more than one of at a time.
+    fn step(self, settings: &mut Settings, by: i32, themes: &[String]) {
+        let mut board = settings.board();
+        let resize = |value: usize| value.saturating_add_signed(by as isize);
+        match self {
+            Row::Difficulty => {
+                settings.difficulty = *cycle(&Difficulty::ALL, &settings.difficulty, by);
+            }
+            Row::Width | Row::Height | Row::Mines => {
+                match self {
+                    Row::Width => board.width = resize(board.width),
+                    Row::Height => board.height = resize(board.height),
+                    _ => board.mines = resize(board.mines),
+                }
+                settings.difficulty = Difficulty::Custom;
+                settings.custom = board.clamped();
+            }
+            Row::FirstClick => {
+                settings.first_click = *cycle(
+                    &[FirstClick::Anything, FirstClick::Safe, FirstClick::Opening],
+                    &settings.first_click,
+                    by,
+                );
+            }
+            Row::QuestionMarks => settings.question_marks = !settings.question_marks,
+            Row::Animations => settings.animations = !settings.animations,
+            Row::Volume => {
+                let volume = settings.volume + VOLUME_STEP * by.signum() as f32;
+                settings.volume = ((volume / VOLUME_STEP).round() * VOLUME_STEP).clamp(0.0, 1.0);
+            }
+            Row::Mute(category) => {
+                if !settings.muted.remove(&category) {
+                    settings.muted.insert(category);
+                }
+            }
+            Row::Skin => {
+                if themes.contains(&settings.theme) {
+                    settings.theme = cycle(themes, &settings.theme, by).clone();
+                } else if let Some(first) = themes.first() {
+                    settings.theme = first.clone();
+                }
+            }
+            Row::Palette => {
+                settings.palette = *cycle(&Palette::ALL, &settings.palette, by);
+            }
+            Row::Scale => {
+                settings.scale = (settings.scale + by.signum() as f32).clamp(1.0, MAX_SCALE);
+            }
+            Row::SwapButtons => settings.swap_buttons = !settings.swap_buttons,
+            Row::LongPress => {
+                let seconds = settings.long_press + LONG_PRESS_STEP * by.signum() as f32;
+                settings.long_press = ((seconds / LONG_PRESS_STEP).round() * LONG_PRESS_STEP)
+                    .clamp(LONG_PRESS_STEP, MAX_LONG_PRESS);
+            }
+        }
+    }
+}
+
+/// The option `by` places along from `current`, wrapping around.
+fn cycle<'a, T: PartialEq>(options: &'a [T], current: &T, by: i32) -> &'a T {
+    let index = options
+        .iter()
+        .position(|option| option == current)
+        .unwrap_or(0);
+    let len = options.len() as i32;
+    &options[(index as i32 + by.signum()).rem_euclid(len) as usize]
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct SettingsMenu {
+    pub open: bool,
+    selected: usize,
+}
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component)]
+struct MenuButton;
+
+/// An arrow either side of a setting, for the row at this index of [`Row::ALL`].
+#[derive(Component)]
+struct StepButton {
+    row: usize,
+    by: i32,
+}
+
+#[derive(Component)]
+struct CloseButton;
+
+pub fn plugin(app: &mut App) {
+    app.insert_resource(Settings::load(std::env::args().skip(1)))
+        .init_resource::<SettingsMenu>()
+        .add_systems(Startup, spawn_button)
+        .add_systems(
+            Update,
+            (
+                // each step only sees presses from before the one that led to it
+                (navigate_menu, click_buttons, open_menu, draw_menu).chain(),
+                (remember_theme, apply_settings).chain(),
+            ),
+        );
+}
+
+fn spawn_button(mut commands: Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                // left of the flag mode button
+                right: Val::Px(BUTTON_MARGIN * 2.0 + BUTTON_SIZE),
+                bottom: Val::Px(BUTTON_MARGIN),
+                width: Val::Px(BUTTON_SIZE),
+                height: Val::Px(BUTTON_SIZE),
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            BackgroundColor(BUTTON_COLOR),
+            MenuButton,
+        ))
+        .with_children(|button| {
+            for _ in 0..3 {
+                button.spawn((
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(3.0),
+                        ..default()
+                    },
+                    BackgroundColor(TEXT_COLOR),
+                ));
+            }
+        });
+}
+
+fn open_menu(
+    actions: Res<ButtonInput<Action>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<MenuButton>)>,
+    mut menu: ResMut<SettingsMenu>,
+    captured: Res<InputCaptured>,
+) {
+    let clicked = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    // not over another menu
+    if (actions.just_pressed(Action::Settings) || clicked) && !captured.0 {
+        menu.open = true;
+    }
+}
+
+/// Runs on raw input, since the menu has the actions to itself while it's open.
+#[allow(clippy::too_many_arguments)]
+fn navigate_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    raw: RawInput,
+    bindings: Res<Bindings>,
+    theme_assets: Res<Assets<Theme>>,
+    themes: Res<Themes>,
+    mut settings: ResMut<Settings>,
+    mut menu: ResMut<SettingsMenu>,
+) {
+    if !menu.open {
+        return;
+    }
+
+    let closing = keys.just_pressed(KeyCode::Escape)
+        || bindings
+            .get(Action::Settings)
+            .iter()
+            .any(|&binding| raw.just_pressed(binding));
+    if closing {
+        menu.open = false;
+        return;
+    }
+
+    let count = Row::ALL.len();
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        menu.selected = (menu.selected + 1) % count;
+    }
+
+    let by = if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        10
+    } else {
+        1
+    };
+    let by = keys.just_pressed(KeyCode::ArrowRight) as i32 * by
+        - keys.just_pressed(KeyCode::ArrowLeft) as i32 * by;
+    if by != 0 {
+        Row::ALL[menu.selected].step(&mut settings, by, &themes.names(&theme_assets));
+        settings.save();
+    }
+}
+
+fn click_buttons(
+    steps: Query<(&Interaction, &StepButton), Changed<Interaction>>,
+    close: Query<&Interaction, (Changed<Interaction>, With<CloseButton>)>,
+    theme_assets: Res<Assets<Theme>>,
+    themes: Res<Themes>,
+    mut settings: ResMut<Settings>,
+    mut menu: ResMut<SettingsMenu>,
+) {
+    if !menu.open {
+        return;
+    }
+
+    for (interaction, step) in steps.iter() {
+        if *interaction == Interaction::Pressed {
+            menu.selected = step.row;
+            Row::ALL[step.row].step(&mut settings, step.by, &themes.names(&theme_assets));
+            settings.save();
+        }
+    }
+
+    if close
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        menu.open = false;
+    }
+}
+
+fn draw_menu(
+    mut commands: Commands,
+    menu: Res<SettingsMenu>,
+    settings: Res<Settings>,
+    roots: Query<Entity, With<MenuRoot>>,
+) {
+    if !menu.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+    if !menu.open {
+        return;
+    }
+
+    let text = |text: String| {
+        (
+            Text::new(text),
+            TextFont {
+                font_size: FONT_SIZE,
+                ..default()
+            },
+            TextColor(TEXT_COLOR),
+        )
+    };
+    let button = || {
+        (
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(0.0)),
+                ..default()
+            },
+            BackgroundColor(ARROW_COLOR),
+        )
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(2.0),
+                ..default()
+            },
+            BackgroundColor(BACKDROP_COLOR),
+            // over the title and pause screens
+            GlobalZIndex(2),
+            MenuRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn(text("Settings".into()));
+
+            for (index, row) in Row::ALL.into_iter().enumerate() {
+                let selected = index == menu.selected;
+                parent
+                    .spawn((
+                        Node {
+                            width: Val::Px(400.0),
+                            justify_content: JustifyContent::SpaceBetween,
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(if selected { SELECTED_COLOR } else { ROW_COLOR }),
+                    ))
+                    .with_children(|line| {
+                        line.spawn(text(row.name().into()));
+                        line.spawn(Node {
+                            column_gap: Val::Px(8.0),
+                            ..default()
+                        })
+                        .with_children(|value| {
+                            value
+                                .spawn((button(), StepButton { row: index, by: -1 }))
+                                .with_child(text("<".into()));
+                            value.spawn((
+                                text(row.value(&settings)),
+                                Node {
+                                    width: Val::Px(160.0),
+                                    justify_content: JustifyContent::Center,
+                                    ..default()
+                                },
+                                TextLayout::new_with_justify(JustifyText::Center),
+                            ));
+                            value
+                                .spawn((button(), StepButton { row: index, by: 1 }))
+                                .with_child(text(">".into()));
+                        });
+                    });
+            }
+
+            parent
+                .spawn((button(), CloseButton))
+                .with_child(text("Close".into()));
+            parent.spawn(text(
+                "Up/Down: select   Left/Right: change (Shift: by 10)   Esc: close".into(),
+            ));
+        });
+}
+
+/// Keeps the skin picked with [`Action::NextTheme`] for next time too.
+fn remember_theme(
+    themes: Res<Themes>,
+    theme_assets: Res<Assets<Theme>>,
+    mut settings: ResMut<Settings>,
+) {
+    if !themes.is_changed() {
+        return;
+    }
+    if let Some(theme) = themes.theme(&theme_assets)
+        && theme.name != settings.theme
+    {
+        settings.theme = theme.name.clone();
+        settings.save();
+    }
+}
+
+/// Puts whatever changed in the settings into effect. A new board size starts a new game, but
+/// puzzles keep their own boards.
+#[allow(clippy::too_many_arguments)]
+fn apply_settings(
+    settings: Res<Settings>,
+    mut applied: Local<Option<Settings>>,
+    puzzle_mode: Option<Res<PuzzleMode>>,
+    theme_assets: Res<Assets<Theme>>,
+    mut minefield: ResMut<Minefield>,
+    mut animations: ResMut<Animations>,
+    mut themes: ResMut<Themes>,
+    mut mouse_settings: ResMut<MouseSettings>,
+    mut touch_settings: ResMut<TouchSettings>,
+    layout: Option<ResMut<Layout>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let old = applied.replace(settings.clone());
+
+    if puzzle_mode.is_none() {
+        // a game under way keeps its board, and the next one gets the new one (see
+        // `crate::puzzle_mode::new_game`)
+        let under_way = minefield.started() && minefield.state == GameState::Playing;
+        let resized = old
+            .as_ref()
+            .is_some_and(|old| old.board() != settings.board());
+        if old.is_none() || resized && !under_way {
+            *minefield = settings.minefield();
+        } else if minefield.rules != settings.rules() {
+            minefield.rules = settings.rules();
+        }
+    }
+
+    if animations.enabled != settings.animations {
+        animations.enabled = settings.animations;
+    }
+
+    if mouse_settings.swap_buttons != settings.swap_buttons {
+        mouse_settings.swap_buttons = settings.swap_buttons;
+    }
+
+    if touch_settings.long_press != settings.long_press {
+        touch_settings.long_press = settings.long_press;
+    }
+
+    if old.as_ref().is_none_or(|old| old.theme != settings.theme)
+        && themes
+            .bypass_change_detection()
+            .request(&settings.theme, &theme_assets)
+    {
+        themes.set_changed();
+    }
+
+    if old.as_ref().is_some_and(|old| old.scale != settings.scale)
+        && let Some(mut layout) = layout
+    {
+        // resizes the window to match
+        layout.set_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(width: usize, height: usize, mines: usize) -> BoardSize {
+        BoardSize {
+            width,
+            height,
+            mines,
+        }
+    }
+
+    #[test]
+    fn one_cell_boards_grow_room_for_a_safe_cell() {
+        assert_eq!(board(1, 1, 1).clamped(), board(1, 2, 1));
+        assert_eq!(board(0, 0, 0).clamped(), board(1, 2, 1));
+        assert_eq!(board(1, 1, 5).clamped(), board(1, 2, 1));
+    }
+
+    #[test]
+    fn strips_keep_one_safe_cell() {
+        assert_eq!(board(1, 10, 50).clamped(), board(1, 10, 9));
+        assert_eq!(board(10, 1, 50).clamped(), board(10, 1, 9));
+        assert_eq!(board(2, 1, 1).clamped(), board(2, 1, 1));
+        assert_eq!(board(1, 10, 0).clamped(), board(1, 10, 1));
+    }
+
+    #[test]
+    fn big_boards_stop_at_the_most_sides_and_mines() {
+        assert_eq!(
+            board(MAX_SIDE, MAX_SIDE, MAX_MINES).clamped(),
+            board(MAX_SIDE, MAX_SIDE, MAX_MINES)
+        );
+        assert_eq!(
+            board(MAX_SIDE + 1, usize::MAX, usize::MAX).clamped(),
+            board(MAX_SIDE, MAX_SIDE, MAX_MINES)
+        );
+        assert_eq!(board(30, 20, 600).clamped(), board(30, 20, 599));
+    }
+}
@@ -16,7 +16,9 @@ struct FpsThresholds {
     low: f64,
 }
 
-pub(super) fn plugin(app: &mut App) {
+/// Public rather than `pub(super)` because the game binary, which turns it on (see `main.rs`),
+/// is a crate of its own now that the modules live in the library.
+pub fn plugin(app: &mut App) {
     app.add_plugins(FrameTimeDiagnosticsPlugin::default())
         .add_systems(Startup, setup_fps_text)
         .add_systems(Update, update_fps_text);
@@ -0,0 +1,135 @@
+use crate::minefield::*;
+use crate::strategy::*;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct SimulationOptions {
+    pub size: (usize, usize),
+    pub mines: usize,
+    pub seeds: Range<u64>,
+    pub first_click: FirstClick,
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        Self {
+            size: MINEFIELD_SIZE,
+            mines: MINE_COUNT,
+            seeds: 0..1000,
+            first_click: FirstClick::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub strategy: String,
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub first_click: FirstClick,
+    pub games: usize,
+    pub wins: usize,
+    pub win_rate: f64,
+    pub average_3bv: f64,
+    pub average_guesses: f64,
+    pub runtime_ms: f64,
+}
+
+/// Plays one game per seed with `strategy`, each through a [`Harness`], stopping early if the
+/// strategy fails. The seed also picks where [`FirstClick`] moves the mines to, so every game
+/// can be played again.
+pub fn simulate(options: &SimulationOptions, strategy: &mut dyn Strategy) -> Report {
+    let mut games = 0;
+    let mut wins = 0;
+    let mut bbbv = 0;
+    let mut guesses = 0;
+    let mut runtime = Duration::ZERO;
+    // the field caps the count to leave a safe cell, so report what was actually played
+    let mut mines = options.mines;
+
+    for seed in options.seeds.clone() {
+        let minefield = Minefield::new(
+            options.size,
+            options.mines,
+            &mut StdRng::seed_from_u64(seed),
+        )
+        .with_rules(Rules {
+            first_click: options.first_click,
+            ..Default::default()
+        })
+        .with_seed(seed);
+        mines = minefield.mine_count;
+        let max_moves = 4 * minefield.rows() * minefield.cols();
+
+        let start = Instant::now();
+        let result = Harness::new(minefield).play(strategy, max_moves);
+        runtime += start.elapsed();
+        if strategy.failed() {
+            log::warn!("{} failed, stopping after {games} games", strategy.name());
+            break;
+        }
+
+        games += 1;
+        bbbv += result.bbbv;
+        guesses += result.guesses;
+        if result.state == GameState::Won {
+            wins += 1;
+        }
+    }
+
+    Report {
+        strategy: strategy.name().to_string(),
+        width: options.size.0,
+        height: options.size.1,
+        mines,
+        first_click: options.first_click,
+        games,
+        wins,
+        win_rate: wins as f64 / games.max(1) as f64,
+        average_3bv: bbbv as f64 / games.max(1) as f64,
+        average_guesses: guesses as f64 / games.max(1) as f64,
+        runtime_ms: runtime.as_secs_f64() * 1000.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_counts_the_mines_played() {
+        let options = SimulationOptions {
+            size: (2, 2),
+            mines: 10,
+            seeds: 0..3,
+            ..Default::default()
+        };
+        let mut strategy = builtin_strategy("logic", 0).unwrap();
+        let report = simulate(&options, strategy.as_mut());
+        assert_eq!(report.mines, 3);
+        assert_eq!(report.games, 3);
+    }
+
+    #[test]
+    fn first_click_rule_is_played() {
+        // one safe cell, which a safe first click always wins on and a random one seldom finds
+        let options = |first_click| SimulationOptions {
+            size: (3, 3),
+            mines: 8,
+            seeds: 0..20,
+            first_click,
+        };
+        let mut strategy = builtin_strategy("random", 0).unwrap();
+
+        let report = simulate(&options(FirstClick::Safe), strategy.as_mut());
+        assert_eq!(report.first_click, FirstClick::Safe);
+        assert_eq!(report.wins, 20);
+
+        let report = simulate(&options(FirstClick::Anything), strategy.as_mut());
+        assert!(report.wins < 20);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::minefield::*;
+use crate::strategy::*;
+use bevy::utils::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolverStrategy {
+    Logic,
+    LogicAndGuess,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolverMove {
+    pub action: MinefieldAction,
+    pub guess: bool,
+}
+
+/// A revealed number's unknown neighbours and how many mines are still hidden among them.
+struct Constraint {
+    cells: HashSet<(usize, usize)>,
+    mines: usize,
+}
+
+/// Picks the next move from the visible board: revealed numbers and flags.
+///
+/// The opening reveal is always made. After that `SolverStrategy::Logic` returns `None` once
+/// nothing more can be deduced, while `SolverStrategy::LogicAndGuess` reveals the unknown cell
+/// least likely to be a mine.
+pub fn next_move(view: &BoardView, strategy: SolverStrategy) -> Option<SolverMove> {
+    if view.state() != GameState::Playing {
+        return None;
+    }
+
+    let mut constraints = vec![];
+    let mut any_revealed = false;
+    for (row, col) in view.positions() {
+        let CellView::Revealed(number) = view.get((row, col)) else {
+            continue;
+        };
+        any_revealed = true;
+
+        let cells: HashSet<_> = view
+            .neighbours((row, col))
+            .filter(|&n| view.get(n) == CellView::Hidden)
+            .collect();
+        if cells.is_empty() {
+            continue;
+        }
+
+        let flagged = view
+            .neighbours((row, col))
+            .filter(|&n| view.get(n) == CellView::Flagged)
+            .count();
+        let Some(mines) = (number as usize).checked_sub(flagged) else {
+            continue;
+        };
+
+        if mines == 0 {
+            return Some(SolverMove {
+                action: MinefieldAction::Chord(row, col),
+                guess: false,
+            });
+        }
+        if mines == cells.len() {
+            let &(r, c) = cells.iter().min().unwrap();
+            return Some(SolverMove {
+                action: MinefieldAction::Flag(r, c),
+                guess: false,
+            });
+        }
+
+        constraints.push(Constraint { cells, mines });
+    }
+
+    if !any_revealed {
+        return opening_move(view).map(|action| SolverMove {
+            action,
+            guess: false,
+        });
+    }
+
+    for a in &constraints {
+        for b in &constraints {
+            if a.cells.len() >= b.cells.len() || !a.cells.is_subset(&b.cells) {
+                continue;
+            }
+
+            let mut rest: Vec<_> = b.cells.difference(&a.cells).copied().collect();
+            rest.sort();
+            let mines = b.mines.saturating_sub(a.mines);
+            if mines == 0 {
+                let (r, c) = rest[0];
+                return Some(SolverMove {
+                    action: MinefieldAction::Reveal(r, c),
+                    guess: false,
+                });
+            }
+            if mines == rest.len() {
+                let (r, c) = rest[0];
+                return Some(SolverMove {
+                    action: MinefieldAction::Flag(r, c),
+                    guess: false,
+                });
+            }
+        }
+    }
+
+    match strategy {
+        SolverStrategy::Logic => None,
+        SolverStrategy::LogicAndGuess => best_guess(view, &constraints).map(|(r, c)| SolverMove {
+            action: MinefieldAction::Reveal(r, c),
+            guess: true,
+        }),
+    }
+}
+
+fn opening_move(view: &BoardView) -> Option<MinefieldAction> {
+    if view.rows() == 0 || view.cols() == 0 {
+        return None;
+    }
+    Some(MinefieldAction::Reveal(view.rows() / 2, view.cols() / 2))
+}
+
+fn best_guess(view: &BoardView, constraints: &[Constraint]) -> Option<(usize, usize)> {
+    let mut unknown = vec![];
+    let mut flagged = 0;
+    for position in view.positions() {
+        match view.get(position) {
+            CellView::Flagged => flagged += 1,
+            CellView::Hidden => unknown.push(position),
+            _ => {}
+        }
+    }
+    if unknown.is_empty() {
+        return None;
+    }
+
+    let density = view.mine_count().saturating_sub(flagged) as f32 / unknown.len() as f32;
+    unknown
+        .into_iter()
+        .map(|cell| {
+            let risk = constraints
+                .iter()
+                .filter(|constraint| constraint.cells.contains(&cell))
+                .map(|constraint| constraint.mines as f32 / constraint.cells.len() as f32)
+                .fold(None, |max: Option<f32>, risk| {
+                    Some(max.map_or(risk, |max| max.max(risk)))
+                })
+                .unwrap_or(density);
+            (cell, risk)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(cell, _)| cell)
+}
@@ -0,0 +1,163 @@
+//! Sound effects for what happens on the field, played at the volume set in the settings screen
+//! unless their category is muted there. Themes can bring their own (see [`crate::theme`]), and
+//! any they leave out come from `assets/sounds`.
+
+use crate::clock::GameClock;
+use crate::minefield::*;
+use crate::settings::Settings;
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Sound {
+    /// A single cell opened.
+    Reveal,
+    /// An opening, with the cells around it.
+    FloodFill,
+    Flag,
+    Chord,
+    Explosion,
+    Win,
+    /// Each second on the clock.
+    Tick,
+}
+
+impl Sound {
+    pub const ALL: [Sound; 7] = [
+        Sound::Reveal,
+        Sound::FloodFill,
+        Sound::Flag,
+        Sound::Chord,
+        Sound::Explosion,
+        Sound::Win,
+        Sound::Tick,
+    ];
+
+    pub fn category(self) -> SoundCategory {
+        match self {
+            Sound::Reveal | Sound::FloodFill | Sound::Chord => SoundCategory::Cells,
+            Sound::Flag => SoundCategory::Flags,
+            Sound::Explosion | Sound::Win => SoundCategory::Endings,
+            Sound::Tick => SoundCategory::Clock,
+        }
+    }
+
+    fn default_path(self) -> &'static str {
+        match self {
+            Sound::Reveal => "sounds/reveal.wav",
+            Sound::FloodFill => "sounds/flood.wav",
+            Sound::Flag => "sounds/flag.wav",
+            Sound::Chord => "sounds/chord.wav",
+            Sound::Explosion => "sounds/explosion.wav",
+            Sound::Win => "sounds/win.wav",
+            Sound::Tick => "sounds/tick.wav",
+        }
+    }
+}
+
+/// Sounds that are muted together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SoundCategory {
+    Cells,
+    Flags,
+    Endings,
+    Clock,
+}
+
+impl SoundCategory {
+    pub fn name(self) -> &'static str {
+        match self {
+            SoundCategory::Cells => "Cell sounds",
+            SoundCategory::Flags => "Flag sounds",
+            SoundCategory::Endings => "Win and loss sounds",
+            SoundCategory::Clock => "Clock tick",
+        }
+    }
+}
+
+/// Each sound's file, relative to `assets`, as a theme lists them.
+pub type SoundPaths = HashMap<Sound, String>;
+
+/// The sounds in use, for the current theme.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Sounds(pub HashMap<Sound, Handle<AudioSource>>);
+
+impl Sounds {
+    /// Loads every sound from `paths`, or from the defaults for those it doesn't have.
+    pub fn load(paths: &SoundPaths, mut load: impl FnMut(String) -> Handle<AudioSource>) -> Self {
+        Self(
+            Sound::ALL
+                .into_iter()
+                .map(|sound| {
+                    let path = paths
+                        .get(&sound)
+                        .cloned()
+                        .unwrap_or_else(|| sound.default_path().to_string());
+                    (sound, load(path))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl FromWorld for Sounds {
+    fn from_world(world: &mut World) -> Self {
+        Self::load(&SoundPaths::default(), |path| world.load_asset(path))
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Sounds>()
+        .add_systems(Update, play_sounds.after(send_minefield_changes));
+}
+
+/// Picks one sound for what the last moves did, the most important first.
+#[allow(clippy::too_many_arguments)]
+fn play_sounds(
+    mut commands: Commands,
+    mut actions: EventReader<MinefieldAction>,
+    mut changes: EventReader<MinefieldChange>,
+    minefield: Res<Minefield>,
+    clock: Res<GameClock>,
+    settings: Res<Settings>,
+    sounds: Res<Sounds>,
+    mut last_second: Local<usize>,
+) {
+    let chorded = actions
+        .read()
+        .any(|action| matches!(action, MinefieldAction::Chord(..)));
+    let (mut revealed, mut flagged) = (0, false);
+    for change in changes.read() {
+        match change {
+            MinefieldChange::Revealed(..) => revealed += 1,
+            MinefieldChange::Flagged(..) => flagged = true,
+            MinefieldChange::Reset | MinefieldChange::MinesLeft(_) => {}
+        }
+    }
+    let ticked = clock.seconds() != *last_second;
+    *last_second = clock.seconds();
+
+    let sound = match minefield.state {
+        GameState::Lost if revealed > 0 => Sound::Explosion,
+        GameState::Won if revealed > 0 || flagged => Sound::Win,
+        _ if revealed > 0 && chorded => Sound::Chord,
+        _ if revealed > 1 => Sound::FloodFill,
+        _ if revealed == 1 => Sound::Reveal,
+        _ if flagged => Sound::Flag,
+        GameState::Playing if ticked && clock.seconds() > 0 => Sound::Tick,
+        _ => return,
+    };
+    if settings.volume <= 0.0 || settings.muted.contains(&sound.category()) {
+        return;
+    }
+    let Some(handle) = sounds.0.get(&sound) else {
+        return;
+    };
+
+    commands.spawn((
+        AudioPlayer(handle.clone()),
+        PlaybackSettings::DESPAWN.with_volume(Volume::new(settings.volume)),
+    ));
+}
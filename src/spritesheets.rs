@@ -1,33 +1,149 @@
 use bevy::prelude::*;
+use serde::Deserialize;
+use std::fmt;
 
-pub const MINEFIELD_SPRITE_SIZE: (f32, f32) = (16.0, 16.0);
-pub const BORDER_SPRITE_SIZE: (f32, f32) = (8.0, 8.0);
-pub const FACE_SPRITE_SIZE: (f32, f32) = (24.0, 24.0);
-pub const SCORE_SPRITE_SIZE: (f32, f32) = (11.0, 20.0);
-pub const SCORE_FRAME_SIZE: (f32, f32) = (35.0, 22.0);
+/// The counters' digits, 0 to 9.
+const DIGIT_COUNT: usize = 10;
+
+/// A grid of equally sized sprites somewhere in the spritesheet.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct SpriteGrid {
+    /// `(width, height)` of each sprite in pixels.
+    pub size: (u32, u32),
+    pub columns: u32,
+    pub rows: u32,
+    /// The top-left corner of the grid in the spritesheet.
+    #[serde(default)]
+    pub offset: (u32, u32),
+}
+
+impl SpriteGrid {
+    pub fn size(&self) -> Vec2 {
+        UVec2::from(self.size).as_vec2()
+    }
+
+    /// How many sprites are in the grid.
+    pub fn count(&self) -> usize {
+        self.columns as usize * self.rows as usize
+    }
+
+    pub fn atlas_layout(&self) -> TextureAtlasLayout {
+        TextureAtlasLayout::from_grid(
+            self.size.into(),
+            self.columns,
+            self.rows,
+            None,
+            Some(self.offset.into()),
+        )
+    }
+}
+
+/// A single sprite somewhere in the spritesheet.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct SpriteRect {
+    pub size: (u32, u32),
+    pub offset: (u32, u32),
+}
+
+impl SpriteRect {
+    pub fn size(&self) -> Vec2 {
+        UVec2::from(self.size).as_vec2()
+    }
+
+    pub fn rect(&self) -> Rect {
+        let min = UVec2::from(self.offset).as_vec2();
+        Rect::from_corners(min, min + self.size())
+    }
+}
+
+/// Where every sprite is in the spritesheet, and so how big everything is drawn. Only there once a
+/// theme has loaded, so nothing is drawn before then.
+#[derive(Resource, Clone, Debug, PartialEq, Deserialize)]
+pub struct SpriteSheetLayout {
+    pub field: SpriteGrid,
+    pub border: SpriteGrid,
+    pub face: SpriteGrid,
+    pub digits: SpriteGrid,
+    pub score_frame: SpriteRect,
+}
+
+impl SpriteSheetLayout {
+    /// Checks that each grid has a sprite for everything drawn from it, since drawing a missing
+    /// one panics.
+    pub fn check(&self) -> Result<(), TooFewSprites> {
+        for (grid, sprites, needed) in [
+            (
+                "field",
+                &self.field,
+                usize::from(MinefieldSpriteIndex::MineMissed) + 1,
+            ),
+            (
+                "border",
+                &self.border,
+                usize::from(BorderSpriteIndex::Empty) + 1,
+            ),
+            ("face", &self.face, usize::from(FaceSpriteIndex::Win) + 1),
+            ("digits", &self.digits, DIGIT_COUNT),
+        ] {
+            if sprites.count() < needed {
+                return Err(TooFewSprites {
+                    grid,
+                    needed,
+                    found: sprites.count(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A grid in a [`SpriteSheetLayout`] without enough sprites.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TooFewSprites {
+    pub grid: &'static str,
+    pub needed: usize,
+    pub found: usize,
+}
+
+impl fmt::Display for TooFewSprites {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the '{}' grid has {} sprites but needs {}",
+            self.grid, self.found, self.needed
+        )
+    }
+}
+
+impl std::error::Error for TooFewSprites {}
+
+#[derive(Resource)]
+pub struct SpriteSheetImage(pub Handle<Image>);
+
+impl FromWorld for SpriteSheetImage {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.load_asset("spritesheet.aseprite#image"))
+    }
+}
+
+/// An empty atlas, which the theme fills in once it loads.
+fn add_atlas_layout(world: &mut World) -> Handle<TextureAtlasLayout> {
+    let mut texture_atlases = world
+        .get_resource_mut::<Assets<TextureAtlasLayout>>()
+        .unwrap();
+    texture_atlases.add(TextureAtlasLayout::new_empty(UVec2::ONE))
+}
 
 #[derive(Resource)]
 pub struct MinefieldSpriteSheet(pub Handle<TextureAtlasLayout>);
 
 impl FromWorld for MinefieldSpriteSheet {
     fn from_world(world: &mut World) -> Self {
-        let texture_atlas = TextureAtlasLayout::from_grid(
-            UVec2::new(
-                MINEFIELD_SPRITE_SIZE.0 as u32,
-                MINEFIELD_SPRITE_SIZE.1 as u32,
-            ),
-            5,
-            3,
-            None,
-            None,
-        );
-        let mut texture_atlases = world
-            .get_resource_mut::<Assets<TextureAtlasLayout>>()
-            .unwrap();
-        Self(texture_atlases.add(texture_atlas))
+        Self(add_atlas_layout(world))
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(usize)]
 pub enum MinefieldSpriteIndex {
     Num,
@@ -56,20 +172,11 @@ pub struct BorderSpriteSheet(pub Handle<TextureAtlasLayout>);
 
 impl FromWorld for BorderSpriteSheet {
     fn from_world(world: &mut World) -> Self {
-        let texture_atlas = TextureAtlasLayout::from_grid(
-            UVec2::new(BORDER_SPRITE_SIZE.0 as u32, BORDER_SPRITE_SIZE.1 as u32),
-            9,
-            1,
-            None,
-            Some((0, 48).into()),
-        );
-        let mut texture_atlases = world
-            .get_resource_mut::<Assets<TextureAtlasLayout>>()
-            .unwrap();
-        Self(texture_atlases.add(texture_atlas))
+        Self(add_atlas_layout(world))
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(usize)]
 pub enum BorderSpriteIndex {
     Vert,
@@ -104,20 +211,11 @@ pub struct FaceSpriteSheet(pub Handle<TextureAtlasLayout>);
 
 impl FromWorld for FaceSpriteSheet {
     fn from_world(world: &mut World) -> Self {
-        let texture_atlas = TextureAtlasLayout::from_grid(
-            UVec2::new(FACE_SPRITE_SIZE.0 as u32, FACE_SPRITE_SIZE.1 as u32),
-            4,
-            1,
-            None,
-            Some((0, 56).into()),
-        );
-        let mut texture_atlases = world
-            .get_resource_mut::<Assets<TextureAtlasLayout>>()
-            .unwrap();
-        Self(texture_atlases.add(texture_atlas))
+        Self(add_atlas_layout(world))
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(usize)]
 pub enum FaceSpriteIndex {
     Idle,
@@ -142,16 +240,6 @@ pub struct ScoreSpriteSheet(pub Handle<TextureAtlasLayout>);
 
 impl FromWorld for ScoreSpriteSheet {
     fn from_world(world: &mut World) -> Self {
-        let texture_atlas = TextureAtlasLayout::from_grid(
-            UVec2::new(SCORE_SPRITE_SIZE.0 as u32, SCORE_SPRITE_SIZE.1 as u32),
-            6,
-            2,
-            None,
-            Some((0, 80).into()),
-        );
-        let mut texture_atlases = world
-            .get_resource_mut::<Assets<TextureAtlasLayout>>()
-            .unwrap();
-        Self(texture_atlases.add(texture_atlas))
+        Self(add_atlas_layout(world))
     }
 }
@@ -0,0 +1,97 @@
+//! Games played and won, and the best winning time, for each board played on, kept in
+//! `statistics.ron` in the config directory (see [`crate::config`]). Only games played by hand
+//! count, so neither autoplay nor puzzles do.
+//!
+//! A game counts as played from its first reveal, so one given up on for a new game counts as
+//! played and not won. Wins that took back a move, were shown a hint or had autoplay's help
+//! still count, but not towards the best time.
+
+use crate::autoplay::Autoplay;
+use crate::clock::{tick_clock, GameClock};
+use crate::config;
+use crate::hint::Hint;
+use crate::minefield::*;
+use crate::puzzle_mode::PuzzleMode;
+use crate::settings::board_name;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const STATISTICS_FILE: &str = "statistics.ron";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// Games with at least one reveal, whether they were finished or not.
+    pub played: usize,
+    pub won: usize,
+    /// Seconds, for the fastest win without any help.
+    pub best_time: Option<f32>,
+}
+
+/// Records by board, as named by [`board_name`].
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Statistics(pub BTreeMap<String, Record>);
+
+impl Statistics {
+    pub fn load() -> Self {
+        config::load(STATISTICS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        config::save(STATISTICS_FILE, self);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.insert_resource(Statistics::load()).add_systems(
+        Update,
+        record_games.after(send_minefield_changes).after(tick_clock),
+    );
+}
+
+/// Adds each game to the record as it starts, and its win if it ends in one.
+#[allow(clippy::too_many_arguments)]
+fn record_games(
+    minefield: Res<Minefield>,
+    clock: Res<GameClock>,
+    autoplay: Res<Autoplay>,
+    hint: Res<Hint>,
+    puzzle_mode: Option<Res<PuzzleMode>>,
+    mut last_state: Local<Option<GameState>>,
+    // whether this game is in the record yet, and whether it had help
+    mut counted: Local<bool>,
+    mut helped: Local<bool>,
+    mut statistics: ResMut<Statistics>,
+) {
+    // a new board, which undoing back to nothing revealed isn't
+    if minefield.is_changed() && !minefield.started() && minefield.undos() == 0 {
+        *counted = false;
+        *helped = false;
+    }
+    if minefield.undos() > 0 || (hint.is_changed() && hint.0.is_some()) || autoplay.enabled {
+        *helped = true;
+    }
+
+    let state = minefield.state;
+    let ended =
+        last_state.replace(state) == Some(GameState::Playing) && state != GameState::Playing;
+    if autoplay.enabled || puzzle_mode.is_some() {
+        return;
+    }
+
+    let name = || board_name(minefield.cols(), minefield.rows(), minefield.mine_count);
+    if minefield.started() && !*counted {
+        *counted = true;
+        statistics.0.entry(name()).or_default().played += 1;
+        statistics.save();
+    }
+    if ended && *counted && state == GameState::Won {
+        let record = statistics.0.entry(name()).or_default();
+        record.won += 1;
+        if !*helped {
+            let time = clock.elapsed.as_secs_f32();
+            record.best_time = Some(record.best_time.map_or(time, |best| best.min(time)));
+        }
+        statistics.save();
+    }
+}
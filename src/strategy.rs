@@ -0,0 +1,428 @@
+use crate::minefield::*;
+use crate::solver::*;
+use crate::spritesheets::MinefieldSpriteIndex;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::fmt;
+use std::task::Poll;
+
+/// What a player can see of a single cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellView {
+    Hidden,
+    Flagged,
+    Revealed(u8),
+    Mine,
+}
+
+/// The player-visible state of a minefield. Strategies only ever get one of these, never the
+/// `Minefield` itself, so they can't peek at where the mines are.
+#[derive(Clone, Debug)]
+pub struct BoardView {
+    cells: Vec<Vec<CellView>>,
+    mine_count: usize,
+    state: GameState,
+}
+
+impl From<&Minefield> for BoardView {
+    fn from(minefield: &Minefield) -> Self {
+        let cells = (0..minefield.rows())
+            .map(|row| {
+                (0..minefield.cols())
+                    .map(|col| {
+                        if minefield.flags[row][col] {
+                            CellView::Flagged
+                        } else if minefield.hidden[row][col] {
+                            CellView::Hidden
+                        } else if minefield.cells[row][col] >= MinefieldSpriteIndex::Mine as u32 {
+                            CellView::Mine
+                        } else {
+                            CellView::Revealed(minefield.cells[row][col] as u8)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            cells,
+            mine_count: minefield.mine_count,
+            state: minefield.state,
+        }
+    }
+}
+
+impl BoardView {
+    pub fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cells.first().map_or(0, |row| row.len())
+    }
+
+    pub fn mine_count(&self) -> usize {
+        self.mine_count
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn get(&self, position: (usize, usize)) -> CellView {
+        self.cells[position.0][position.1]
+    }
+
+    pub fn contains(&self, position: (usize, usize)) -> bool {
+        position.0 < self.rows() && position.1 < self.cols()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let cols = self.cols();
+        (0..self.rows()).flat_map(move |row| (0..cols).map(move |col| (row, col)))
+    }
+
+    pub fn neighbours(&self, position: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        neighbours(position, (self.rows(), self.cols()))
+    }
+}
+
+/// A player that picks moves from the visible board.
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Called before the first move of every game.
+    fn new_game(&mut self, _view: &BoardView) {}
+
+    /// The next move, or `None` to give up on this game.
+    fn next_move(&mut self, view: &BoardView) -> Option<SolverMove>;
+
+    /// Like [`Strategy::next_move`], but for callers that can't wait on it: `Pending` while the
+    /// move is still being worked out, to be asked again later with the same view.
+    fn poll_move(&mut self, view: &BoardView) -> Poll<Option<SolverMove>> {
+        Poll::Ready(self.next_move(view))
+    }
+
+    /// Whether the strategy broke down for good, such as a bot that stopped replying, and so
+    /// won't make any more moves.
+    fn failed(&self) -> bool {
+        false
+    }
+
+    /// Called once a game is over, or after giving up on it.
+    fn end_game(&mut self, _view: &BoardView) {}
+}
+
+impl Strategy for SolverStrategy {
+    fn name(&self) -> &str {
+        match self {
+            SolverStrategy::Logic => "logic",
+            SolverStrategy::LogicAndGuess => "guess",
+        }
+    }
+
+    fn next_move(&mut self, view: &BoardView) -> Option<SolverMove> {
+        next_move(view, *self)
+    }
+}
+
+/// Reveals a random unknown cell every move.
+pub struct RandomStrategy {
+    rng: StdRng,
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn next_move(&mut self, view: &BoardView) -> Option<SolverMove> {
+        view.positions()
+            .filter(|&position| view.get(position) == CellView::Hidden)
+            .choose(&mut self.rng)
+            .map(|(row, col)| SolverMove {
+                action: MinefieldAction::Reveal(row, col),
+                guess: true,
+            })
+    }
+}
+
+pub const BUILTIN_STRATEGIES: [&str; 3] = ["logic", "guess", "random"];
+
+pub fn builtin_strategy(name: &str, seed: u64) -> Option<Box<dyn Strategy>> {
+    match name {
+        "logic" => Some(Box::new(SolverStrategy::Logic)),
+        "guess" => Some(Box::new(SolverStrategy::LogicAndGuess)),
+        "random" => Some(Box::new(RandomStrategy::new(seed))),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidMove {
+    OutOfBounds(MinefieldAction),
+    /// A move that does nothing to the cell it's made on, like revealing a flag or chording a
+    /// hidden cell.
+    WrongCell(MinefieldAction, CellView),
+}
+
+impl fmt::Display for InvalidMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidMove::OutOfBounds(action) => write!(f, "{action:?} is outside the board"),
+            InvalidMove::WrongCell(action, cell) => {
+                write!(f, "{action:?} can't be made on a {cell:?} cell")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidMove {}
+
+/// Checks that a move is on the board and on a cell it can change: reveals on hidden cells,
+/// flags on hidden or flagged ones and chords on numbers.
+pub fn validate_move(view: &BoardView, action: MinefieldAction) -> Result<(), InvalidMove> {
+    let (MinefieldAction::Reveal(row, col)
+    | MinefieldAction::Flag(row, col)
+    | MinefieldAction::Chord(row, col)) = action;
+
+    if !view.contains((row, col)) {
+        return Err(InvalidMove::OutOfBounds(action));
+    }
+    let cell = view.get((row, col));
+    let allowed = match action {
+        MinefieldAction::Reveal(..) => cell == CellView::Hidden,
+        MinefieldAction::Flag(..) => matches!(cell, CellView::Hidden | CellView::Flagged),
+        MinefieldAction::Chord(..) => matches!(cell, CellView::Revealed(_)),
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(InvalidMove::WrongCell(action, cell))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    pub state: GameState,
+    pub moves: usize,
+    pub guesses: usize,
+    /// The [`Minefield::bbbv`] of the board as it was played, after the first reveal moved any
+    /// mines.
+    pub bbbv: usize,
+}
+
+/// Plays a strategy against a minefield it never gets to see directly.
+pub struct Harness {
+    minefield: Minefield,
+    moves: usize,
+    guesses: usize,
+    bbbv: Option<usize>,
+}
+
+impl Harness {
+    pub fn new(minefield: Minefield) -> Self {
+        Self {
+            minefield,
+            moves: 0,
+            guesses: 0,
+            bbbv: None,
+        }
+    }
+
+    pub fn view(&self) -> BoardView {
+        BoardView::from(&self.minefield)
+    }
+
+    pub fn state(&self) -> GameState {
+        self.minefield.state
+    }
+
+    /// Asks the strategy for one move and applies it. Returns `Ok(false)` once the game is over
+    /// or the strategy gives up.
+    pub fn step(&mut self, strategy: &mut dyn Strategy) -> Result<bool, InvalidMove> {
+        if self.minefield.state != GameState::Playing {
+            return Ok(false);
+        }
+
+        let view = self.view();
+        let Some(solver_move) = strategy.next_move(&view) else {
+            return Ok(false);
+        };
+        validate_move(&view, solver_move.action)?;
+
+        self.moves += 1;
+        if solver_move.guess {
+            self.guesses += 1;
+        }
+        // the board's 3BV is only known once the mines have stopped moving, and the move that
+        // settles them can end the game and change the board
+        if let MinefieldAction::Reveal(row, col) = solver_move.action {
+            self.minefield.settle_mines((row, col));
+        }
+        if self.bbbv.is_none() && self.minefield.started() {
+            self.bbbv = Some(self.minefield.bbbv());
+        }
+        self.minefield.apply(solver_move.action);
+
+        Ok(true)
+    }
+
+    /// Plays until the game ends, the strategy gives up or makes an invalid move, or `max_moves`
+    /// moves have been made. Only a won game counts as a win.
+    pub fn play(&mut self, strategy: &mut dyn Strategy, max_moves: usize) -> GameResult {
+        strategy.new_game(&self.view());
+
+        while self.moves < max_moves {
+            match self.step(strategy) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    log::warn!("{} made an invalid move: {e}", strategy.name());
+                    break;
+                }
+            }
+        }
+        strategy.end_game(&self.view());
+
+        GameResult {
+            state: self.minefield.state,
+            moves: self.moves,
+            guesses: self.guesses,
+            bbbv: self.bbbv.unwrap_or_else(|| self.minefield.bbbv()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes the given moves in order, then gives up.
+    struct Scripted(Vec<SolverMove>);
+
+    impl Strategy for Scripted {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn next_move(&mut self, _view: &BoardView) -> Option<SolverMove> {
+            (!self.0.is_empty()).then(|| self.0.remove(0))
+        }
+    }
+
+    fn moves(actions: &[(MinefieldAction, bool)]) -> Scripted {
+        Scripted(
+            actions
+                .iter()
+                .map(|&(action, guess)| SolverMove { action, guess })
+                .collect(),
+        )
+    }
+
+    fn harness(rows: &[&str]) -> Harness {
+        let mines: Vec<Vec<bool>> = rows
+            .iter()
+            .map(|row| row.chars().map(|c| c == '*').collect())
+            .collect();
+        Harness::new(Minefield::from_mines(&mines))
+    }
+
+    #[test]
+    fn rejects_moves_outside_the_board() {
+        let view = harness(&["*..", "..."]).view();
+        for action in [
+            MinefieldAction::Reveal(2, 0),
+            MinefieldAction::Flag(0, 3),
+            MinefieldAction::Chord(5, 5),
+        ] {
+            assert_eq!(
+                validate_move(&view, action),
+                Err(InvalidMove::OutOfBounds(action))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_moves_on_cells_they_cant_change() {
+        let mut harness = harness(&["*....", "*...*"]);
+        let mut strategy = moves(&[
+            (MinefieldAction::Flag(0, 0), false),
+            (MinefieldAction::Reveal(0, 2), false),
+        ]);
+        while harness.step(&mut strategy) == Ok(true) {}
+        let view = harness.view();
+
+        let revealed = CellView::Revealed(0);
+        for (action, cell) in [
+            (MinefieldAction::Reveal(0, 2), revealed),
+            (MinefieldAction::Reveal(0, 0), CellView::Flagged),
+            (MinefieldAction::Flag(0, 2), revealed),
+            (MinefieldAction::Chord(1, 0), CellView::Hidden),
+            (MinefieldAction::Chord(0, 0), CellView::Flagged),
+        ] {
+            assert_eq!(
+                validate_move(&view, action),
+                Err(InvalidMove::WrongCell(action, cell))
+            );
+        }
+        assert_eq!(validate_move(&view, MinefieldAction::Flag(0, 0)), Ok(()));
+        assert_eq!(validate_move(&view, MinefieldAction::Chord(0, 1)), Ok(()));
+    }
+
+    #[test]
+    fn invalid_moves_end_the_game_uncounted() {
+        let mut harness = harness(&["*..", "..."]);
+        let mut strategy = moves(&[
+            (MinefieldAction::Reveal(1, 2), true),
+            (MinefieldAction::Reveal(1, 2), false),
+            (MinefieldAction::Flag(0, 0), false),
+        ]);
+        let result = harness.play(&mut strategy, 10);
+        assert_eq!(result.state, GameState::Playing);
+        assert_eq!(result.moves, 1);
+        assert_eq!(result.guesses, 1);
+        assert_ne!(harness.view().get((0, 0)), CellView::Flagged);
+    }
+
+    #[test]
+    fn counts_moves_and_guesses() {
+        let mut harness = harness(&["*.*", "..."]);
+        let mut strategy = moves(&[
+            (MinefieldAction::Reveal(1, 0), true),
+            (MinefieldAction::Flag(0, 0), false),
+            (MinefieldAction::Reveal(0, 1), true),
+            (MinefieldAction::Reveal(1, 1), false),
+            (MinefieldAction::Reveal(1, 2), false),
+        ]);
+        let result = harness.play(&mut strategy, 10);
+        assert_eq!(result.state, GameState::Won);
+        assert_eq!(result.moves, 5);
+        assert_eq!(result.guesses, 2);
+    }
+
+    #[test]
+    fn stops_at_max_moves() {
+        let mut harness = harness(&["*....", "....."]);
+        // flagging and unflagging the same cell for as long as it's allowed to
+        let mut strategy = Scripted(vec![
+            SolverMove {
+                action: MinefieldAction::Flag(1, 4),
+                guess: false,
+            };
+            100
+        ]);
+        let result = harness.play(&mut strategy, 7);
+        assert_eq!(result.state, GameState::Playing);
+        assert_eq!(result.moves, 7);
+        assert_eq!(strategy.0.len(), 93);
+    }
+}
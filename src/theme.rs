@@ -0,0 +1,334 @@
+//! Swappable skins. Every `*.theme.ron` under `assets/themes` is a skin, naming a spritesheet
+//! image (relative to `assets`) and where each sprite is in it:
+//!
+//! ```ron
+//! (
+//!     name: "night",
+//!     image: "themes/night.png",
+//!     sprites: (
+//!         field: (size: (16, 16), columns: 5, rows: 3),
+//!         border: (size: (8, 8), columns: 9, rows: 1, offset: (0, 48)),
+//!         face: (size: (24, 24), columns: 4, rows: 1, offset: (0, 56)),
+//!         digits: (size: (11, 20), columns: 6, rows: 2, offset: (0, 80)),
+//!         score_frame: (size: (35, 22), offset: (0, 120)),
+//!     ),
+//! )
+//! ```
+//!
+//! The image can also be an Aseprite file, in which case `sprites` can be left out to use its
+//! slices instead (see [`crate::aseprite`]).
+//!
+//! A theme can also swap out any of the sound effects (see [`crate::sound`]), with paths relative
+//! to `assets` too:
+//!
+//! ```ron
+//!     sounds: {
+//!         Explosion: "themes/night-explosion.wav",
+//!     },
+//! ```
+//!
+//! Pick one in the settings screen or with `--theme=<name>` (see [`crate::settings`]), or cycle
+//! through them with [`Action::NextTheme`] (T). With the `dev` feature, saving the current theme
+//! or its image updates the running game.
+
+use crate::aseprite::*;
+use crate::input::Action;
+use crate::sound::*;
+use crate::spritesheets::*;
+use crate::tilemap::MinefieldMaterial;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadDirectError, LoadedFolder};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fmt;
+
+pub const DEFAULT_THEME: &str = "classic";
+
+#[derive(Asset, TypePath, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub image: Handle<Image>,
+    pub sprites: SpriteSheetLayout,
+    pub sounds: Sounds,
+}
+
+#[derive(Deserialize)]
+struct ThemeManifest {
+    name: String,
+    image: String,
+    #[serde(default)]
+    sprites: Option<SpriteSheetLayout>,
+    #[serde(default)]
+    sounds: SoundPaths,
+}
+
+#[derive(Default)]
+struct ThemeLoader;
+
+#[derive(Debug)]
+pub enum ThemeLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Aseprite(LoadDirectError),
+    NoSprites,
+    TooFewSprites(TooFewSprites),
+}
+
+impl fmt::Display for ThemeLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to read theme: {e}"),
+            Self::Ron(e) => write!(f, "failed to parse theme: {e}"),
+            Self::Aseprite(e) => write!(f, "failed to load the theme's spritesheet: {e}"),
+            Self::NoSprites => write!(
+                f,
+                "theme has no sprites and its image isn't an Aseprite file"
+            ),
+            Self::TooFewSprites(e) => write!(f, "theme's sprites don't fit: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ThemeLoaderError {}
+
+impl AssetLoader for ThemeLoader {
+    type Asset = Theme;
+    type Settings = ();
+    type Error = ThemeLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Theme, ThemeLoaderError> {
+        let mut bytes = vec![];
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(ThemeLoaderError::Io)?;
+        let manifest: ThemeManifest = ron::de::from_bytes(&bytes).map_err(ThemeLoaderError::Ron)?;
+
+        let aseprite = manifest.image.ends_with(".aseprite") || manifest.image.ends_with(".ase");
+        let sprites = match manifest.sprites {
+            Some(sprites) => sprites,
+            None if aseprite => {
+                let sheet = load_context
+                    .loader()
+                    .immediate()
+                    .load::<AsepriteSheet>(&manifest.image)
+                    .await
+                    .map_err(ThemeLoaderError::Aseprite)?;
+                sheet.get().sprites.clone()
+            }
+            None => return Err(ThemeLoaderError::NoSprites),
+        };
+        sprites.check().map_err(ThemeLoaderError::TooFewSprites)?;
+
+        let image = AssetPath::from(manifest.image);
+        let image = if aseprite {
+            image.with_label("image")
+        } else {
+            image
+        };
+
+        Ok(Theme {
+            name: manifest.name,
+            image: load_context.load(image),
+            sprites,
+            sounds: Sounds::load(&manifest.sounds, |path| load_context.load(path)),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
+
+/// The installed skins, sorted by name, and which one is in use.
+#[derive(Resource)]
+pub struct Themes {
+    folder: Handle<LoadedFolder>,
+    pub themes: Vec<Handle<Theme>>,
+    pub current: Option<usize>,
+    requested: String,
+}
+
+impl Themes {
+    pub fn theme<'a>(&self, themes: &'a Assets<Theme>) -> Option<&'a Theme> {
+        themes.get(&self.themes[self.current?])
+    }
+
+    pub fn names(&self, themes: &Assets<Theme>) -> Vec<String> {
+        self.themes
+            .iter()
+            .filter_map(|handle| Some(themes.get(handle)?.name.clone()))
+            .collect()
+    }
+
+    /// Switches to the theme called `name`, now if it's loaded or else once the themes are.
+    /// Returns whether it switched now.
+    pub fn request(&mut self, name: &str, themes: &Assets<Theme>) -> bool {
+        self.requested = name.to_string();
+        let found = self
+            .themes
+            .iter()
+            .position(|handle| themes.get(handle).is_some_and(|theme| theme.name == name));
+        let switched = found.is_some() && found != self.current;
+        if switched {
+            self.current = found;
+        }
+        switched
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_asset::<AsepriteSheet>()
+        .init_asset_loader::<AsepriteLoader>()
+        .init_asset::<Theme>()
+        .init_asset_loader::<ThemeLoader>()
+        .init_resource::<SpriteSheetImage>()
+        .init_resource::<MinefieldSpriteSheet>()
+        .init_resource::<BorderSpriteSheet>()
+        .init_resource::<FaceSpriteSheet>()
+        .init_resource::<ScoreSpriteSheet>();
+
+    let folder = app.world().resource::<AssetServer>().load_folder("themes");
+    app.insert_resource(Themes {
+        folder,
+        themes: vec![],
+        current: None,
+        requested: DEFAULT_THEME.to_string(),
+    })
+    .add_systems(Update, (collect_themes, cycle_theme, apply_theme).chain());
+
+    #[cfg(feature = "dev")]
+    app.add_systems(Update, reload_theme.before(apply_theme));
+}
+
+fn collect_themes(
+    mut events: EventReader<AssetEvent<LoadedFolder>>,
+    folders: Res<Assets<LoadedFolder>>,
+    theme_assets: Res<Assets<Theme>>,
+    mut themes: ResMut<Themes>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&themes.folder) {
+            continue;
+        }
+        let Some(folder) = folders.get(&themes.folder) else {
+            continue;
+        };
+
+        let mut found: Vec<(String, Handle<Theme>)> = folder
+            .handles
+            .iter()
+            .filter_map(|handle| handle.clone().try_typed::<Theme>().ok())
+            .filter_map(|handle| Some((theme_assets.get(&handle)?.name.clone(), handle)))
+            .collect();
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // nothing is drawn without a theme, so any will do if the one asked for isn't there
+        let position = |wanted: &str| found.iter().position(|(name, _)| name == wanted);
+        let current = position(&themes.requested);
+        let fallback = position(DEFAULT_THEME).or((!found.is_empty()).then_some(0));
+        let current = if current.is_some() {
+            current
+        } else if let Some(fallback) = fallback {
+            warn!(
+                "No theme named '{}', using '{}' instead",
+                themes.requested, found[fallback].0
+            );
+            Some(fallback)
+        } else {
+            error!("No themes found in assets/themes, so there's nothing to draw the board with");
+            None
+        };
+        themes.themes = found.into_iter().map(|(_, handle)| handle).collect();
+        themes.current = current;
+    }
+}
+
+fn cycle_theme(actions: Res<ButtonInput<Action>>, mut themes: ResMut<Themes>) {
+    if actions.just_pressed(Action::NextTheme) && !themes.themes.is_empty() {
+        let next = themes.current.map_or(0, |current| current + 1) % themes.themes.len();
+        themes.current = Some(next);
+    }
+}
+
+/// Points the spritesheet resources at the current theme, which redraws the board with it.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_theme(
+    mut commands: Commands,
+    themes: Res<Themes>,
+    theme_assets: Res<Assets<Theme>>,
+    mut image: ResMut<SpriteSheetImage>,
+    sprites: Option<ResMut<SpriteSheetLayout>>,
+    mut atlases: ResMut<Assets<TextureAtlasLayout>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    sheets: (
+        Res<MinefieldSpriteSheet>,
+        Res<BorderSpriteSheet>,
+        Res<FaceSpriteSheet>,
+        Res<ScoreSpriteSheet>,
+    ),
+    material: Res<MinefieldMaterial>,
+    mut sounds: ResMut<Sounds>,
+) {
+    if !themes.is_changed() {
+        return;
+    }
+    let Some(theme) = themes.theme(&theme_assets) else {
+        return;
+    };
+    info!("Using theme '{}'", theme.name);
+
+    let (field, border, face, digits) = sheets;
+    for (handle, grid) in [
+        (&field.0, &theme.sprites.field),
+        (&border.0, &theme.sprites.border),
+        (&face.0, &theme.sprites.face),
+        (&digits.0, &theme.sprites.digits),
+    ] {
+        atlases.insert(handle, grid.atlas_layout());
+    }
+
+    image.0 = theme.image.clone();
+    if let Some(material) = materials.get_mut(&material.0) {
+        material.texture = Some(theme.image.clone());
+    }
+    // the board is only rebuilt if any sprites moved, and otherwise just retextured
+    match sprites {
+        Some(mut sprites) => {
+            sprites.set_if_neq(theme.sprites.clone());
+        }
+        None => commands.insert_resource(theme.sprites.clone()),
+    }
+    sounds.set_if_neq(theme.sounds.clone());
+}
+
+/// Re-applies the current theme when its manifest or image changes on disk.
+#[cfg(feature = "dev")]
+fn reload_theme(
+    mut theme_events: EventReader<AssetEvent<Theme>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    image: Res<SpriteSheetImage>,
+    material: Res<MinefieldMaterial>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut themes: ResMut<Themes>,
+) {
+    let current = themes.current.map(|current| themes.themes[current].id());
+    if theme_events
+        .read()
+        .any(|event| matches!(event, AssetEvent::Modified { id } if Some(*id) == current))
+    {
+        info!("Reloading theme");
+        themes.set_changed();
+    }
+
+    if image_events.read().any(|event| event.is_modified(&image.0)) {
+        info!("Reloading spritesheet");
+        // the field's material only picks up the new texture when it is touched
+        materials.get_mut(&material.0);
+    }
+}
@@ -0,0 +1,363 @@
+//! Draws the minefield as one mesh per chunk of cells instead of one sprite per cell, so big
+//! boards stay cheap to render and only the chunks whose cells changed get touched.
+
+use crate::layout::Layout;
+use crate::minefield::*;
+use crate::spritesheets::*;
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::utils::HashSet;
+
+/// Cells along each side of a chunk.
+pub const CHUNK_SIZE: usize = 32;
+
+const QUESTION_MARK_COLOR: Color = Color::srgb(0.2, 0.3, 0.8);
+
+#[derive(Resource)]
+pub struct MinefieldMaterial(pub Handle<ColorMaterial>);
+
+impl FromWorld for MinefieldMaterial {
+    fn from_world(world: &mut World) -> Self {
+        let texture = world.resource::<SpriteSheetImage>().0.clone();
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        Self(materials.add(texture))
+    }
+}
+
+/// Cells that keep showing their old sprite until an animation gets to them.
+#[derive(Resource, Default)]
+pub struct HeldCells(pub HashSet<(usize, usize)>);
+
+/// Hidden cells drawn pressed in, as if already revealed, while a button is held over them.
+#[derive(Resource, Default)]
+pub struct PressedCells(pub HashSet<(usize, usize)>);
+
+/// Asks for a cell to be drawn again, after it stops being held.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RedrawCell(pub usize, pub usize);
+
+/// The `?` drawn over a question-marked cell, as spritesheets don't have one.
+#[derive(Component)]
+struct QuestionMark((usize, usize));
+
+/// A block of cells drawn as one mesh, remembering which sprite each cell showed last.
+#[derive(Component)]
+pub struct MinefieldChunk {
+    /// `(row, col)` of the top-left cell.
+    pub origin: (usize, usize),
+    /// `(width, height)` in cells, smaller than [`CHUNK_SIZE`] along the right and bottom edges.
+    pub size: (usize, usize),
+    pub indices: Vec<usize>,
+}
+
+impl MinefieldChunk {
+    pub fn new(origin: (usize, usize), size: (usize, usize), minefield: &Minefield) -> Self {
+        let mut chunk = Self {
+            origin,
+            size,
+            indices: vec![],
+        };
+        chunk.indices = chunk
+            .positions()
+            .map(|p| sprite_index(minefield, p))
+            .collect();
+        chunk
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (origin, size) = (self.origin, self.size);
+        (0..size.1)
+            .flat_map(move |row| (0..size.0).map(move |col| (origin.0 + row, origin.1 + col)))
+    }
+
+    /// A quad per cell, with its top-left corner at the chunk's origin.
+    pub fn mesh(&self, cell_size: Vec2, atlas: &TextureAtlasLayout, image_size: Vec2) -> Mesh {
+        let mut positions = Vec::with_capacity(self.indices.len() * 4);
+        let mut triangles = Vec::with_capacity(self.indices.len() * 6);
+        for row in 0..self.size.1 {
+            for col in 0..self.size.0 {
+                let x = col as f32 * cell_size.x;
+                let y = -(row as f32) * cell_size.y;
+                let (right, bottom) = (x + cell_size.x, y - cell_size.y);
+
+                let first = positions.len() as u32;
+                positions.extend([
+                    [x, y, 0.0],
+                    [right, y, 0.0],
+                    [right, bottom, 0.0],
+                    [x, bottom, 0.0],
+                ]);
+                triangles.extend([0, 3, 2, 0, 2, 1].map(|i| first + i));
+            }
+        }
+
+        // kept in the main world too, so the UVs can be swapped later
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs(atlas, image_size))
+        .with_inserted_indices(Indices::U32(triangles))
+    }
+
+    /// Atlas rects are in pixels of the whole image, which can be bigger than the atlas grid.
+    fn uvs(&self, atlas: &TextureAtlasLayout, image_size: Vec2) -> Vec<[f32; 2]> {
+        self.indices
+            .iter()
+            .flat_map(|&index| {
+                let rect = atlas.textures[index].as_rect();
+                let (min, max) = (rect.min / image_size, rect.max / image_size);
+                [
+                    [min.x, min.y],
+                    [max.x, min.y],
+                    [max.x, max.y],
+                    [min.x, max.y],
+                ]
+            })
+            .collect()
+    }
+}
+
+pub fn sprite_index(minefield: &Minefield, (row, col): (usize, usize)) -> usize {
+    const MINE: u32 = MinefieldSpriteIndex::Mine as u32;
+    const MINE_HIT: u32 = MinefieldSpriteIndex::MineHit as u32;
+    const MINE_MISSED: u32 = MinefieldSpriteIndex::MineMissed as u32;
+
+    if minefield.flags[row][col] {
+        MinefieldSpriteIndex::Flag.into()
+    } else if minefield.hidden[row][col] {
+        MinefieldSpriteIndex::Hidden.into()
+    } else {
+        match minefield.cells[row][col] {
+            MINE => MinefieldSpriteIndex::Mine.into(),
+            MINE_HIT => MinefieldSpriteIndex::MineHit.into(),
+            MINE_MISSED => MinefieldSpriteIndex::MineMissed.into(),
+            n => usize::from(MinefieldSpriteIndex::Num) + n as usize,
+        }
+    }
+}
+
+/// The origin of the chunk holding a cell.
+pub fn chunk_origin((row, col): (usize, usize)) -> (usize, usize) {
+    (row - row % CHUNK_SIZE, col - col % CHUNK_SIZE)
+}
+
+/// The `(origin, size)` of each chunk covering a board of `(width, height)` cells.
+pub fn chunks(
+    (width, height): (usize, usize),
+) -> impl Iterator<Item = ((usize, usize), (usize, usize))> {
+    (0..height).step_by(CHUNK_SIZE).flat_map(move |row| {
+        (0..width).step_by(CHUNK_SIZE).map(move |col| {
+            (
+                (row, col),
+                (CHUNK_SIZE.min(width - col), CHUNK_SIZE.min(height - row)),
+            )
+        })
+    })
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<MinefieldMaterial>()
+        .init_resource::<HeldCells>()
+        .init_resource::<PressedCells>()
+        .add_event::<RedrawCell>()
+        .add_systems(
+            Update,
+            (
+                update_minefield_chunks,
+                draw_question_marks.run_if(resource_exists::<Layout>),
+            )
+                .after(send_minefield_changes),
+        );
+}
+
+/// Swaps the UVs of the chunks with changed cells, or of every chunk when the field was reset.
+#[allow(clippy::too_many_arguments)]
+pub fn update_minefield_chunks(
+    mut changes: EventReader<MinefieldChange>,
+    mut redraws: EventReader<RedrawCell>,
+    held: Res<HeldCells>,
+    pressed: Res<PressedCells>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    minefield: Res<Minefield>,
+    sprite_sheet: Res<MinefieldSpriteSheet>,
+    image: Res<SpriteSheetImage>,
+    images: Res<Assets<Image>>,
+    atlases: Res<Assets<TextureAtlasLayout>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut chunks: Query<(&mut MinefieldChunk, &Mesh2d)>,
+) {
+    // chunks spawned before the image loaded have placeholder UVs, and a reloaded or swapped
+    // image can be a different size
+    let mut reset = image_events
+        .read()
+        .any(|event| event.is_loaded_with_dependencies(&image.0) || event.is_modified(&image.0))
+        || image.is_changed();
+    let mut dirty = HashSet::new();
+    for change in changes.read() {
+        match *change {
+            MinefieldChange::Reset => reset = true,
+            MinefieldChange::Revealed(row, col) | MinefieldChange::Flagged(row, col) => {
+                dirty.insert(chunk_origin((row, col)));
+            }
+            MinefieldChange::MinesLeft(_) => {}
+        }
+    }
+    for &RedrawCell(row, col) in redraws.read() {
+        dirty.insert(chunk_origin((row, col)));
+    }
+    if !reset && dirty.is_empty() {
+        return;
+    }
+    let (Some(atlas), Some(image)) = (atlases.get(&sprite_sheet.0), images.get(&image.0)) else {
+        return;
+    };
+    let image_size = image.size().as_vec2();
+
+    for (mut chunk, mesh) in chunks.iter_mut() {
+        if !reset && !dirty.contains(&chunk.origin) {
+            continue;
+        }
+        if chunk.origin.0 + chunk.size.1 > minefield.rows()
+            || chunk.origin.1 + chunk.size.0 > minefield.cols()
+        {
+            // stale until the board is rebuilt for the new size
+            continue;
+        }
+
+        let indices: Vec<usize> = chunk
+            .positions()
+            .zip(&chunk.indices)
+            .map(|(position, &shown)| {
+                if held.0.contains(&position) {
+                    shown
+                } else if pressed.0.contains(&position) {
+                    MinefieldSpriteIndex::Num.into()
+                } else {
+                    sprite_index(&minefield, position)
+                }
+            })
+            .collect();
+        if chunk.indices == indices && !reset {
+            continue;
+        }
+
+        chunk.indices = indices;
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, chunk.uvs(atlas, image_size));
+        }
+    }
+}
+
+/// Keeps a [`QuestionMark`] over every hidden cell with a mark and no flag.
+fn draw_question_marks(
+    mut commands: Commands,
+    mut changes: EventReader<MinefieldChange>,
+    minefield: Res<Minefield>,
+    layout: Res<Layout>,
+    marks: Query<(Entity, &QuestionMark)>,
+) {
+    let mut changed = HashSet::new();
+    let mut reset = layout.is_changed();
+    for change in changes.read() {
+        match *change {
+            MinefieldChange::Reset => reset = true,
+            MinefieldChange::Revealed(row, col) | MinefieldChange::Flagged(row, col) => {
+                changed.insert((row, col));
+            }
+            MinefieldChange::MinesLeft(_) => {}
+        }
+    }
+
+    for (entity, mark) in marks.iter() {
+        if reset || changed.contains(&mark.0) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    let cells: Box<dyn Iterator<Item = (usize, usize)> + '_> = if reset {
+        Box::new(minefield.positions())
+    } else {
+        Box::new(changed.into_iter())
+    };
+
+    let size = layout.sprites.field.size();
+    for (row, col) in cells {
+        if row >= minefield.rows()
+            || col >= minefield.cols()
+            || !minefield.marks[row][col]
+            || !minefield.hidden[row][col]
+            || minefield.flags[row][col]
+        {
+            continue;
+        }
+        let centre = layout.cell_position((row, col)) + Vec2::new(size.x, -size.y) / 2.0;
+        commands.spawn((
+            Text2d::new("?"),
+            TextFont {
+                font_size: size.y * 0.8,
+                ..default()
+            },
+            TextColor(QUESTION_MARK_COLOR),
+            Transform::from_translation(centre.extend(0.4)),
+            QuestionMark((row, col)),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_cover_a_strip() {
+        assert_eq!(
+            chunks((33, 1)).collect::<Vec<_>>(),
+            [((0, 0), (32, 1)), ((0, 32), (1, 1))]
+        );
+        assert_eq!(chunk_origin((0, 31)), (0, 0));
+        assert_eq!(chunk_origin((0, 32)), (0, 32));
+    }
+
+    #[test]
+    fn chunks_cover_a_big_board_once() {
+        let chunks: Vec<_> = chunks((1000, 1000)).collect();
+        assert_eq!(chunks.len(), 32 * 32);
+        assert_eq!(chunks.last(), Some(&((992, 992), (8, 8))));
+        assert_eq!(
+            chunks.iter().map(|(_, (w, h))| w * h).sum::<usize>(),
+            1000 * 1000
+        );
+
+        // every cell's chunk origin is the chunk that covers it
+        for position in [(0, 0), (31, 32), (500, 999), (999, 0), (999, 999)] {
+            let origin = chunk_origin(position);
+            let (_, size) = chunks.iter().find(|(chunk, _)| *chunk == origin).unwrap();
+            assert!(position.0 < origin.0 + size.1 && position.1 < origin.1 + size.0);
+        }
+        assert_eq!(chunk_origin((999, 999)), (992, 992));
+    }
+
+    #[test]
+    fn shows_the_mines_a_loss_uncovers() {
+        let mut minefield = Minefield::from_mines(&[vec![true, false, true, false]]);
+        minefield.apply(MinefieldAction::Flag(0, 3));
+        minefield.apply(MinefieldAction::Reveal(0, 0));
+
+        let sprites: Vec<usize> = minefield
+            .positions()
+            .map(|position| sprite_index(&minefield, position))
+            .collect();
+        assert_eq!(
+            sprites,
+            [
+                MinefieldSpriteIndex::MineHit.into(),
+                usize::from(MinefieldSpriteIndex::Hidden),
+                MinefieldSpriteIndex::Mine.into(),
+                MinefieldSpriteIndex::MineMissed.into(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Playing on a touchscreen: a tap reveals (or chords, on an opened cell) and a long press
+//! flags, with the cell filling up while it's held. Flag mode swaps the two. Two fingers pinch
+//! and drag the camera (see [`crate::viewport`]). The long press delay is in the settings
+//! screen (see [`crate::settings`]).
+
+use crate::flag_mode::FlagMode;
+use crate::input::InputCaptured;
+use crate::layout::Layout;
+use crate::minefield::*;
+use crate::pointer::CellPicker;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+pub const DEFAULT_LONG_PRESS: f32 = 0.4;
+/// How far a finger can move, in logical pixels, before it stops being a tap.
+const TAP_SLOP: f32 = 10.0;
+const PROGRESS_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.4);
+
+/// Kept in step with the settings screen (see [`crate::settings::Settings::long_press`]).
+#[derive(Resource, Debug)]
+pub struct TouchSettings {
+    /// Seconds a finger has to stay down to flag instead of reveal.
+    pub long_press: f32,
+}
+
+impl Default for TouchSettings {
+    fn default() -> Self {
+        Self {
+            long_press: DEFAULT_LONG_PRESS,
+        }
+    }
+}
+
+/// The finger on the board, while it could still turn out to be a tap or a long press.
+#[derive(Resource, Default)]
+struct Press(Option<Pressing>);
+
+struct Pressing {
+    id: u64,
+    cell: (usize, usize),
+    held_for: f32,
+}
+
+#[derive(Component)]
+struct Progress;
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<TouchSettings>()
+        .init_resource::<Press>()
+        .add_systems(Startup, spawn_progress)
+        .add_systems(
+            Update,
+            (handle_touches, show_progress)
+                .chain()
+                .run_if(resource_exists::<Layout>),
+        );
+}
+
+fn spawn_progress(mut commands: Commands) {
+    commands.spawn((
+        Sprite {
+            color: PROGRESS_COLOR,
+            anchor: Anchor::BottomLeft,
+            ..default()
+        },
+        Transform::default(),
+        Visibility::Hidden,
+        Progress,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_touches(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    settings: Res<TouchSettings>,
+    captured: Res<InputCaptured>,
+    flag_mode: Res<FlagMode>,
+    picker: CellPicker,
+    minefield: Res<Minefield>,
+    mut press: ResMut<Press>,
+    mut actions: EventWriter<MinefieldAction>,
+) {
+    // a second finger means a pinch
+    if captured.0 || touches.iter().count() > 1 {
+        press.0 = None;
+        return;
+    }
+
+    if press.0.is_none()
+        && let Some(touch) = touches.iter_just_pressed().next()
+        && let Some(cell) = picker.cell_at(touch.position())
+    {
+        press.0 = Some(Pressing {
+            id: touch.id(),
+            cell,
+            held_for: 0.0,
+        });
+    }
+
+    let Some(pressing) = &mut press.0 else {
+        return;
+    };
+    let (row, col) = pressing.cell;
+    if row >= minefield.rows() || col >= minefield.cols() {
+        press.0 = None;
+        return;
+    }
+    let reveal = if minefield.hidden[row][col] {
+        MinefieldAction::Reveal(row, col)
+    } else {
+        MinefieldAction::Chord(row, col)
+    };
+    let flag = if flag_mode.0 && !minefield.hidden[row][col] {
+        MinefieldAction::Chord(row, col)
+    } else {
+        MinefieldAction::Flag(row, col)
+    };
+    let (tap, long_press) = if flag_mode.0 {
+        (flag, reveal)
+    } else {
+        (reveal, flag)
+    };
+
+    if let Some(touch) = touches.get_pressed(pressing.id) {
+        if touch.distance().length() > TAP_SLOP {
+            press.0 = None;
+            return;
+        }
+        pressing.held_for += time.delta_secs();
+        if pressing.held_for >= settings.long_press {
+            actions.send(long_press);
+            press.0 = None;
+        }
+    } else {
+        // released before it became a long press, unless it was cancelled
+        if touches.just_released(pressing.id) {
+            actions.send(tap);
+        }
+        press.0 = None;
+    }
+}
+
+fn show_progress(
+    press: Res<Press>,
+    settings: Res<TouchSettings>,
+    layout: Res<Layout>,
+    minefield: Res<Minefield>,
+    mut progress: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<Progress>>,
+) {
+    for (mut sprite, mut transform, mut visibility) in progress.iter_mut() {
+        match &press.0 {
+            Some(pressing) if minefield.hidden[pressing.cell.0][pressing.cell.1] => {
+                let size = layout.sprites.field.size();
+                let filled = (pressing.held_for / settings.long_press).min(1.0);
+                sprite.custom_size = Some(Vec2::new(size.x, size.y * filled));
+                transform.translation =
+                    (layout.cell_position(pressing.cell) - Vec2::new(0.0, size.y)).extend(0.7);
+                *visibility = Visibility::Visible;
+            }
+            _ => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use crate::constants::*;
+use crate::input::*;
+use crate::layout::Layout;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::render::camera::ClearColorConfig;
+use bevy::render::view::{Layer, RenderLayers};
+use bevy::sprite::Anchor;
+use bevy::window::PrimaryWindow;
+use rand::prelude::*;
+
+/// The render layer for the header, which the HUD camera draws on top of the board so it stays
+/// put while the board is panned and zoomed.
+pub const HUD_LAYER: Layer = 1;
+
+const ZOOM_STEP: f32 = 1.25;
+const MAX_ZOOM: f32 = 8.0;
+/// Logical pixels per second.
+const KEY_PAN_SPEED: f32 = 600.0;
+/// How far the pointer can move while a drag is held, in logical pixels, before a click becomes
+/// a drag.
+const DRAG_THRESHOLD: f32 = 4.0;
+/// Scroll deltas in pixels are roughly this many per line.
+const PIXELS_PER_LINE: f32 = 40.0;
+const SHAKE_SECONDS: f32 = 0.4;
+/// How far the board shakes at first, in world units.
+const SHAKE_STRENGTH: f32 = 3.0;
+
+/// How many physical pixels each world unit takes up when the board fits the window.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct PixelScale(pub f32);
+
+impl Default for PixelScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+#[derive(Component)]
+pub struct BoardCamera;
+
+#[derive(Component)]
+pub struct HudCamera;
+
+/// Covers the strip behind the header so the board doesn't show through when panned under it.
+#[derive(Component)]
+struct HudBackdrop;
+
+/// The player's zoom and pan on top of the fitted view, reset whenever the board changes size.
+#[derive(Resource, Debug)]
+pub struct CameraControl {
+    /// Multiplies the fitted [`PixelScale`].
+    pub zoom: f32,
+    /// World units the view has moved from where the fit puts it.
+    pub pan: Vec2,
+    /// Whether the input just released was dragging the board rather than clicking it.
+    pub dragged: bool,
+    drag: Option<(Action, Vec2, Vec2)>,
+    /// The bottom edge of the HUD in physical pixels, measured up from the bottom of the window.
+    hud_bottom: f32,
+    /// The layout size the zoom and pan were for.
+    layout_size: Vec2,
+    /// Seconds of screen shake left.
+    shake: f32,
+}
+
+impl Default for CameraControl {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+            dragged: false,
+            drag: None,
+            hud_bottom: f32::INFINITY,
+            layout_size: Vec2::ZERO,
+            shake: 0.0,
+        }
+    }
+}
+
+impl CameraControl {
+    /// Shakes the board, but not the HUD, for a moment.
+    pub fn shake(&mut self) {
+        self.shake = SHAKE_SECONDS;
+    }
+
+    pub fn stop_shaking(&mut self) {
+        self.shake = 0.0;
+    }
+
+    /// Whether a cursor position, in logical window coordinates, is over the HUD.
+    pub fn over_hud(&self, window: &Window, cursor: Vec2) -> bool {
+        window.physical_height() as f32 - cursor.y * window.scale_factor() >= self.hud_bottom
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<PixelScale>()
+        .init_resource::<CameraControl>()
+        .add_systems(Startup, spawn_cameras)
+        .add_systems(
+            Update,
+            (control_camera, fit_camera_to_window)
+                .chain()
+                .run_if(resource_exists::<Layout>),
+        );
+}
+
+fn spawn_cameras(mut commands: Commands) {
+    let projection = OrthographicProjection {
+        far: 1000.0,
+        near: -1000.0,
+        viewport_origin: Vec2::ZERO,
+        scale: 1.0 / SCALE,
+        ..OrthographicProjection::default_2d()
+    };
+
+    commands.spawn((Camera2d, projection.clone(), BoardCamera));
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: 1,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        projection,
+        RenderLayers::layer(HUD_LAYER),
+        // menus go over everything
+        IsDefaultUiCamera,
+        HudCamera,
+    ));
+
+    commands.spawn((
+        Sprite {
+            color: BACKGROUND_COLOR,
+            anchor: Anchor::TopLeft,
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, -1.0),
+        RenderLayers::layer(HUD_LAYER),
+        HudBackdrop,
+    ));
+}
+
+/// Where the bottom-left of the board goes, in physical pixels, for a given scale: centred, but
+/// keeping the left edge and the header on screen when the board doesn't fit.
+fn board_offset(physical: Vec2, size: Vec2, scale: f32) -> Vec2 {
+    let spare = physical - size * scale;
+    Vec2::new(
+        (spare.x / 2.0).floor().max(0.0),
+        if spare.y < 0.0 {
+            spare.y
+        } else {
+            (spare.y / 2.0).floor()
+        },
+    )
+}
+
+/// The board camera's translation for a fitted scale plus the player's zoom and pan.
+fn board_translation(physical: Vec2, size: Vec2, scale: f32, control: &CameraControl) -> Vec2 {
+    let scale = scale * control.zoom;
+    -board_offset(physical, size, scale) / scale + control.pan
+}
+
+fn fit_zoom(physical: Vec2, size: Vec2, scale: f32) -> f32 {
+    (physical / size).min_element() / scale
+}
+
+/// Mouse wheel and the zoom actions zoom, [`Action::Drag`], reveal with [`Action::RevealDrags`]
+/// held and the cursor actions with [`Action::Pan`] held pan, and zoom to fit fits the whole
+/// board back in the window. On a touchscreen, two fingers pinch and drag.
+#[allow(clippy::too_many_arguments)]
+pub fn control_camera(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    layout: Res<Layout>,
+    pixel_scale: Res<PixelScale>,
+    time: Res<Time>,
+    actions: Res<ButtonInput<Action>>,
+    captured: Res<InputCaptured>,
+    touches: Res<Touches>,
+    mut wheel: EventReader<MouseWheel>,
+    mut control: ResMut<CameraControl>,
+) {
+    if captured.0 {
+        wheel.clear();
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let physical = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    if physical.min_element() <= 0.0 {
+        return;
+    }
+    let scale_factor = window.scale_factor();
+    let min_zoom = fit_zoom(physical, layout.size, pixel_scale.0).min(1.0);
+
+    // cursor in physical pixels from the bottom-left, to match the camera
+    let to_physical = |cursor: Vec2| Vec2::new(cursor.x, window.height() - cursor.y) * scale_factor;
+    let cursor = window.cursor_position();
+
+    let wheel_by: f32 = wheel
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    let mut zoom_by = wheel_by;
+    if actions.just_pressed(Action::ZoomIn) {
+        zoom_by += 1.0;
+    }
+    if actions.just_pressed(Action::ZoomOut) {
+        zoom_by -= 1.0;
+    }
+
+    // how far the two fingers of a pinch spread apart, and where the middle of them moved from
+    let mut pinch = None;
+    if let [first, second] = touches.iter().collect::<Vec<_>>()[..] {
+        let before = (
+            first.position() - first.delta(),
+            second.position() - second.delta(),
+        );
+        let spread = first.position().distance(second.position()) / before.0.distance(before.1);
+        if spread.is_finite() && spread > 0.0 {
+            zoom_by += spread.ln() / ZOOM_STEP.ln();
+        }
+        pinch = Some((
+            (first.position() + second.position()) / 2.0,
+            (before.0 + before.1) / 2.0,
+        ));
+    }
+
+    if zoom_by != 0.0 {
+        // keep the world position under the pinch, the cursor or the middle of the window where
+        // it is
+        let anchor = match (pinch, cursor) {
+            (Some((middle, _)), _) => to_physical(middle),
+            (None, Some(cursor)) if wheel_by != 0.0 => to_physical(cursor),
+            _ => physical / 2.0,
+        };
+        let before = board_translation(physical, layout.size, pixel_scale.0, &control)
+            + anchor / (pixel_scale.0 * control.zoom);
+
+        let zoom = (control.zoom * ZOOM_STEP.powf(zoom_by)).clamp(min_zoom, MAX_ZOOM);
+        control.zoom = zoom;
+        control.pan = Vec2::ZERO;
+        let after = board_translation(physical, layout.size, pixel_scale.0, &control)
+            + anchor / (pixel_scale.0 * zoom);
+        control.pan = before - after;
+    }
+
+    if actions.just_pressed(Action::ZoomToFit) {
+        control.zoom = fit_zoom(physical, layout.size, pixel_scale.0).min(MAX_ZOOM);
+        control.pan = Vec2::ZERO;
+    }
+
+    let world_per_logical = scale_factor / (pixel_scale.0 * control.zoom);
+
+    // without pan held they move the keyboard cursor
+    let held = |action| actions.pressed(action) as i32 as f32;
+    let direction = held(Action::Pan)
+        * Vec2::new(
+            held(Action::CursorRight) - held(Action::CursorLeft),
+            held(Action::CursorUp) - held(Action::CursorDown),
+        );
+    control.pan += direction * KEY_PAN_SPEED * time.delta_secs() * world_per_logical;
+    if let Some((middle, before)) = pinch {
+        let delta = middle - before;
+        control.pan -= Vec2::new(delta.x, -delta.y) * world_per_logical;
+    }
+
+    if let Some(cursor) = cursor {
+        for action in [Action::Drag, Action::Reveal] {
+            let panning = action == Action::Drag || actions.pressed(Action::RevealDrags);
+            if actions.just_pressed(action) && panning {
+                control.drag = Some((action, cursor, cursor));
+                control.dragged = action == Action::Reveal;
+            }
+        }
+
+        if let Some((action, start, last)) = control.drag {
+            if cursor.distance(start) > DRAG_THRESHOLD {
+                control.dragged = true;
+            }
+            if control.dragged {
+                let delta = cursor - last;
+                control.pan -= Vec2::new(delta.x, -delta.y) * world_per_logical;
+            }
+            control.drag = Some((action, start, cursor));
+        }
+    }
+
+    if let Some((action, ..)) = control.drag
+        && !actions.pressed(action)
+    {
+        control.drag = None;
+    } else if control.drag.is_none() && actions.get_just_pressed().next().is_some() {
+        // any new input, so a flag right after a drag still counts
+        control.dragged = false;
+    }
+
+    // don't lose the board entirely
+    let pan = control.pan.clamp(-layout.size, layout.size);
+    if control.pan != pan {
+        control.pan = pan;
+    }
+}
+
+/// Picks the largest integer scale the board fits in and centres it, leaving the rest of the
+/// window as letterboxing in the clear colour. Works in physical pixels so HiDPI displays get
+/// crisp pixels too.
+///
+/// The board camera then applies the player's zoom and pan, while the HUD camera keeps the
+/// header at the fitted scale, shrinking it only if it's wider than the window.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn fit_camera_to_window(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    time: Res<Time>,
+    layout: Res<Layout>,
+    mut pixel_scale: ResMut<PixelScale>,
+    mut control: ResMut<CameraControl>,
+    mut board_camera: Query<
+        (&mut OrthographicProjection, &mut Transform),
+        (With<BoardCamera>, Without<HudCamera>),
+    >,
+    mut hud_camera: Query<
+        (&mut OrthographicProjection, &mut Transform),
+        (With<HudCamera>, Without<BoardCamera>),
+    >,
+    mut backdrop: Query<
+        (&mut Sprite, &mut Transform),
+        (With<HudBackdrop>, Without<BoardCamera>, Without<HudCamera>),
+    >,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let physical = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    if physical.min_element() <= 0.0 {
+        // minimized
+        return;
+    }
+
+    if control.layout_size != layout.size {
+        control.zoom = 1.0;
+        control.pan = Vec2::ZERO;
+        control.layout_size = layout.size;
+    }
+
+    let scale = (physical / layout.size).min_element().floor().max(1.0);
+    pixel_scale.set_if_neq(PixelScale(scale));
+
+    let board_scale = scale * control.zoom;
+    let mut translation = board_translation(physical, layout.size, scale, &control);
+    if control.shake > 0.0 {
+        let strength = SHAKE_STRENGTH * control.shake / SHAKE_SECONDS;
+        let mut rng = thread_rng();
+        translation += Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0)) * strength;
+        control.shake = (control.shake - time.delta_secs()).max(0.0);
+    }
+    for (projection, transform) in board_camera.iter_mut() {
+        set_camera(
+            projection,
+            transform,
+            window.scale_factor() / board_scale,
+            translation,
+        );
+    }
+
+    let hud_scale = scale.min(physical.x / layout.size.x);
+    let top =
+        (board_offset(physical, layout.size, scale).y + layout.size.y * scale).min(physical.y);
+    let hud_offset = Vec2::new(
+        ((physical.x - layout.size.x * hud_scale) / 2.0)
+            .floor()
+            .max(0.0),
+        top - layout.size.y * hud_scale,
+    );
+    let hud_translation = -hud_offset / hud_scale;
+    for (projection, transform) in hud_camera.iter_mut() {
+        set_camera(
+            projection,
+            transform,
+            window.scale_factor() / hud_scale,
+            hud_translation,
+        );
+    }
+
+    let hud_bottom = top - layout.header_height() * hud_scale;
+    if control.hud_bottom != hud_bottom {
+        control.hud_bottom = hud_bottom;
+    }
+
+    // from the top of the window down to the bottom of the header, all the way across
+    let window_top = hud_translation.y + physical.y / hud_scale;
+    let backdrop_size = Vec2::new(
+        physical.x / hud_scale,
+        window_top - (layout.size.y - layout.header_height()),
+    );
+    for (mut sprite, mut transform) in backdrop.iter_mut() {
+        if sprite.custom_size != Some(backdrop_size) {
+            sprite.custom_size = Some(backdrop_size);
+        }
+        let position = Vec2::new(hud_translation.x, window_top);
+        if transform.translation.truncate() != position {
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+}
+
+fn set_camera(
+    mut projection: Mut<OrthographicProjection>,
+    mut transform: Mut<Transform>,
+    scale: f32,
+    translation: Vec2,
+) {
+    if projection.scale != scale {
+        projection.scale = scale;
+    }
+    if transform.translation.truncate() != translation {
+        transform.translation = translation.extend(transform.translation.z);
+    }
+}